@@ -7,7 +7,7 @@ import { ComputeBudgetProgram, Connection, Keypair, PublicKey, Transaction, Tran
 import { useLocalStorage } from "usehooks-ts";
 import {encode, decode} from 'bs58';
 import toml from 'toml';
-import {randomU64, ixPack, ixWasmToJs, getDistance} from '../util/util';
+import {randomU64, ixWasmToJs, getDistance} from '../util/util';
 import { Stage, Container } from 'react-pixi-fiber'
 import { WasmTile, WasmPlayer, NavEnum, Blueprints, PlayPauseState, WasmTroop } from '../util/interfaces';
 import * as PIXI from 'pixi.js';
@@ -18,6 +18,10 @@ import {Layout} from "@solana/buffer-layout";
 import { Idl, IdlTypeDef, IdlEvent } from "@project-serum/anchor/dist/cjs/idl";
 import { IdlCoder } from "@project-serum/anchor/dist/cjs/coder/borsh/idl";
 const DominariIDL:Idl = require("../../target/idl/dominari.json");
+// Tiles per system_init_tiles instruction, each one is a CPI so keep it under the compute limit
+const TILES_PER_IX = 8;
+// Times to rebuild a tx that made an entity after someone else took the id it was built for
+const ENTITY_ID_RETRIES = 3;
 import { sha256 } from "js-sha256";
 import { Event, EventData } from "@project-serum/anchor";
 
@@ -25,6 +29,23 @@ import { Event, EventData } from "@project-serum/anchor";
 import NoSSR from 'react-no-ssr';
 import { ConfigFileInterface } from "../util/interfaces";
 
+// Entity ids are only predicted, if another entity is made first the tx fails without changing anything
+// so re-read the counter and build it again for the new id
+const sendWithNextEntityId = async (connection:Connection, gamestate:GameState, buildTx:(entityId:bigint) => Promise<Transaction>) => {
+    for(let attempt = 0; attempt < ENTITY_ID_RETRIES; attempt++) {
+        await gamestate.update_instance_index();
+        const entityId:bigint = gamestate.get_next_entity_id();
+        const tx = await buildTx(entityId);
+        const sig = await connection.sendRawTransaction(tx.serialize(), {skipPreflight: true});
+        const result = await connection.confirmTransaction(sig);
+        if(!result.value.err) {
+            return {sig, entityId};
+        }
+        console.log(`TX ${sig} failed for entity id ${entityId}: `, result.value.err);
+    }
+    throw new Error("Couldn't get an entity id, try again");
+}
+
 export const DominariContext = createContext({} as DominariContextInterface);
 export interface DominariContextInterface {
     connection: Connection,
@@ -211,7 +232,8 @@ const Settings = () => {
         */
 
         // Read Config File
-        let configFile:ConfigFileInterface = toml.parse(await configFileRef.current?.files?.item(0)?.text() as string)
+        const configFileText = await configFileRef.current?.files?.item(0)?.text() as string;
+        let configFile:ConfigFileInterface = toml.parse(configFileText)
         configFile.config.starting_cards = configFile.config.starting_cards.map((val:string) => {
             // it can either be turned into a PublicKey, in which case, leave it as is
             // or get blueprint key from the name
@@ -238,58 +260,7 @@ const Settings = () => {
         tx.sign([privateKey]);
         const sig = await connection.sendRawTransaction(tx.serialize(), {skipPreflight: true});
         console.log(sig);
-        
-
-        // Init Map 
-        const mapId = randomU64();
-        const initMapIx = ixWasmToJs(dominari.init_map(privateKey.publicKey.toString(), newInstanceId, mapId, configFile.map.mapmeta.max_x, configFile.map.mapmeta.max_y));
-        const tx2 = new VersionedTransaction(new TransactionMessage({
-            payerKey: privateKey.publicKey,
-            recentBlockhash: (await connection.getLatestBlockhash()).blockhash,
-            instructions: [
-                initMapIx
-            ]
-        }).compileToLegacyMessage());
-        tx2.sign([privateKey]);
-        const sig2 = await connection.sendRawTransaction(tx2.serialize(), {skipPreflight: true});
-        console.log(sig2);
-
-
-        const recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
-        let tileIxGroup = [];
-        // Init Tiles
-        for(let x=0; x<configFile.map.mapmeta.max_x; x++){
-            for(let y=0; y<configFile.map.mapmeta.max_y; y++){
-                let tileId = randomU64();
-                const initTileTx = dominari.init_tile(privateKey.publicKey.toString(), newInstanceId, tileId, x, y, BigInt(configFile.map.cost_per_tile.toString()))
-                tileIxGroup.push(ixWasmToJs(initTileTx));
-            }
-        }
-        let ixPacked = await ixPack(tileIxGroup);
-        let txGroup = []
-        for (let ixP of ixPacked){
-            const tx = new Transaction();
-            tx.add(...ixP);
-            tx.recentBlockhash = recentBlockhash;
-            tx.feePayer = privateKey.publicKey;
-            tx.sign(privateKey)
-            txGroup.push(tx);
-        }
-        console.log("Creating Game Instance, Map, and Tiles TX: ", txGroup);
-        let promises = txGroup.map((tx) => {
-            return connection.sendRawTransaction(tx.serialize(), {skipPreflight: true})
-        });
-        let sigs = await Promise.all(promises)
-        let confirmations = sigs.map((sig) => {
-            return connection.confirmTransaction(sig);
-        });
-        await Promise.all(confirmations);
-        console.log("All tiles created!");
-
-        // Init Features
-            // Build Index
-            // Fetch Entites
-            // Sort through them to Find Feature Tiles
+        await connection.confirmTransaction(sig);
 
         let gamestate = new GameState(
             connection.rpcEndpoint,
@@ -297,37 +268,50 @@ const Settings = () => {
             REGISTRY_PROGRAM_ID.toString(),
             newInstanceId //not using the full form cause the update doesn't happen til after the rerender
         );
+        await gamestate.update_instance_index();
 
-        await gamestate.load_state();
-        setPlayPause(gamestate.get_play_phase());
+        // Init Map, Tiles and Features from the [map] table of the config
+        // The program hands out entity ids in order, so every tx has to land before the next one goes out
+        const mapPlan = dominari.plan_map_template(
+            privateKey.publicKey.toString(),
+            newInstanceId,
+            gamestate.get_next_entity_id(),
+            configFileText,
+            TILES_PER_IX
+        );
 
-        
-        let featureIxG = [];
-        for(let feature of configFile.map.features) {
-            let tile_id = BigInt(gamestate.get_tile_id(feature.x, feature.y));
-            let featureIx = ixWasmToJs(dominari.init_feature(
-                privateKey.publicKey.toString(),
-                newInstanceId,
-                randomU64(),
-                tile_id,
-                feature.feature
-            ));
-            featureIxG.push(featureIx);
-        }
-        (await ixPack(featureIxG))
-        .map((ixG) => {
+        const recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
+        const toTx = (ixs:any[]) => {
             const tx = new Transaction();
-            tx.add(...ixG);
+            tx.add(ComputeBudgetProgram.setComputeUnitLimit({units: 1400000}));
+            tx.add(...ixs.map(ixWasmToJs));
             tx.recentBlockhash = recentBlockhash;
             tx.feePayer = privateKey.publicKey;
-            tx.sign(privateKey);
+            tx.sign(privateKey)
             return tx;
-        })
-        .forEach(async (tx) => {
-            const sig = await connection.sendRawTransaction(tx.serialize(), {skipPreflight: true})
-            console.log("Feature Tx: ", sig);
-            await connection.confirmTransaction(sig);
-        })
+        }
+        // A failed tx means someone made an entity while the map was building and the rest of the plan has the wrong ids
+        // Plans can't be patched up after that, so stop and make a new instance
+        const sendInOrder = async (txs:Transaction[]) => {
+            for (let tx of txs) {
+                const sig = await connection.sendRawTransaction(tx.serialize(), {skipPreflight: true});
+                const result = await connection.confirmTransaction(sig);
+                if(result.value.err) {
+                    toast.error(`Map TX ${sig} failed, create a new instance`);
+                    throw new Error(`Map TX ${sig} failed: ${JSON.stringify(result.value.err)}`);
+                }
+            }
+        }
+        console.log("Creating Map, Tiles and Features: ", mapPlan);
+        await sendInOrder([toTx(mapPlan.map)]);
+        await sendInOrder(mapPlan.tiles.map(toTx));
+        console.log("All tiles created!");
+        await sendInOrder(mapPlan.features.map(toTx));
+        console.log("All features created!");
+        await sendInOrder(mapPlan.spawn_zones.map(toTx));
+
+        await gamestate.load_state();
+        setPlayPause(gamestate.get_play_phase());
 
         let oldInstanceStr:string[] | null | undefined = JSON.parse(instanceListStr);
         if(oldInstanceStr) {
@@ -515,6 +499,7 @@ const MapPage = () => {
 
                 switch(event.name) {
                     case "GameStateChanged": 
+                    case "PhaseExpired":
                         if(event.data.newState.play){
                             setPlayPause("Play");
                         } else if (event.data.newState.paused){
@@ -548,6 +533,7 @@ const MapPage = () => {
                         break;
                     case "TileAttacked": 
                         await gamestate.update_entity(BigInt(event.data.attacker));
+                        await gamestate.update_entity(BigInt(event.data.attackingTile));
                         await gamestate.update_entity(BigInt(event.data.defender));
                         await gamestate.update_entity(BigInt(event.data.defendingTile));
                         renderMap();
@@ -564,6 +550,15 @@ const MapPage = () => {
                             // Defending Unit on Tile X,Y Received Damage
                             toast(`Your ${defender.name} at (${defendingTile.x}, ${defendingTile.y}) took ${event.data.damage} damage from ${attacker.name}`, {icon: '☠'})
                         }
+
+                        // Defender struck back
+                        if(event.data.counterDamage != null){
+                            if(attacker.troop_owner_player_key == privateKey.publicKey.toString()){
+                                toast(`Your ${attacker.name} took ${event.data.counterDamage} damage from ${defender.name} striking back`, {icon: '☠'})
+                            } else if (defender.troop_owner_player_key == privateKey.publicKey.toString()){
+                                toast(`${defender.name} struck back at ${attacker.name} for ${event.data.counterDamage} damage`, {icon: '⚔'})
+                            }
+                        }
                         
                         break;
                 }
@@ -655,12 +650,45 @@ const MapPage = () => {
                 if(selectedTroopTile.troop && tile.troop && getDistance(selectedTroopTile.x, selectedTroopTile.y, tile.x, tile.y) <= selectedTroopTile.troop.attack_range ){
                     // This tile is within attack range of the selected tile's troop
                     console.log("Attacking Unit!");
+                    // Commit to a secret first, the attack reveals it in a later slot
+                    const secret = crypto.getRandomValues(new Uint8Array(32));
+                    const commitIx = ixWasmToJs(dominari.commit_randomness(
+                        privateKey.publicKey.toString(),
+                        gamestate.instance,
+                        dominari.get_commitment(secret),
+                        BigInt(selectedTroopTile.troop.id),
+                        BigInt(tile.troop.id),
+                    ));
+                    const commitTx = new VersionedTransaction(new TransactionMessage({
+                        payerKey: privateKey.publicKey,
+                        recentBlockhash: (await connection.getLatestBlockhash()).blockhash,
+                        instructions: [commitIx]
+                    }).compileToLegacyMessage());
+                    commitTx.sign([privateKey]);
+                    try{
+                        const commitSig = await connection.sendRawTransaction(commitTx.serialize());
+                        await connection.confirmTransaction(commitSig, "confirmed");
+                    } catch (e){
+                        console.log(e);
+                        return;
+                    }
+
+                    // Roll uses the hash of the slot after the commitment and a newer one from when the attack lands, wait until both are in SlotHashes
+                    const commitSlot = await connection.getSlot("confirmed");
+                    while(await connection.getSlot("confirmed") <= commitSlot + 2){
+                        await new Promise(resolve => setTimeout(resolve, 400));
+                    }
+
                     const attackIx = ixWasmToJs(dominari.attack_unit(
                         privateKey.publicKey.toString(),
                         gamestate.instance,
                         BigInt(selectedTroopTile.troop.id),
+                        BigInt(selectedTroopTile.troop.troop_owner_player_id),
+                        BigInt(gamestate.get_tile_id(selectedTroopTile.x, selectedTroopTile.y)),
                         BigInt(tile.troop.id),
                         BigInt(gamestate.get_tile_id(tile.x, tile.y)),
+                        BigInt(tile.troop.troop_owner_player_id),
+                        secret,
                     ));
 
                     const tx = new VersionedTransaction(new TransactionMessage({
@@ -682,12 +710,17 @@ const MapPage = () => {
                 } else if(selectedTroopTile.troop && getDistance(selectedTroopTile.x, selectedTroopTile.y, tile.x, tile.y) <= selectedTroopTile.troop.movement) {
                     // Tile doesn't have a troop and is within movement range of the selected Troop
 
+                    const fromTileId = BigInt(gamestate.get_tile_id(selectedTroopTile.x, selectedTroopTile.y));
+                    const toTileId = BigInt(gamestate.get_tile_id(tile.x, tile.y));
+                    // Throws if every path is blocked or too long
+                    const path = gamestate.get_move_path(BigInt(selectedTroopTile.troop.id), fromTileId, toTileId);
                     const moveIx = ixWasmToJs(dominari.move_unit(
                         privateKey.publicKey.toString(),
                         gamestate.instance,
                         BigInt(selectedTroopTile.troop.id),
-                        BigInt(gamestate.get_tile_id(selectedTroopTile.x, selectedTroopTile.y)),
-                        BigInt(gamestate.get_tile_id(tile.x, tile.y)),
+                        fromTileId,
+                        toTileId,
+                        path,
                     ));
 
                     const tx = new VersionedTransaction(new TransactionMessage({
@@ -735,7 +768,10 @@ const MapPage = () => {
 
     // Functions
     const renderMap = () => {
-        let grid: WasmTile[] = gamestate.get_map();
+        // Spectators see everything, players only what their side can see
+        // Looked up here since event listeners hold on to the first render's state
+        let me: WasmPlayer | undefined = gamestate.get_player_info(privateKey.publicKey.toString());
+        let grid: WasmTile[] = me ? gamestate.get_visible_map(BigInt(me.id)) : gamestate.get_map();
         for(let tile of grid){
             renderTile(tile);
         }
@@ -816,7 +852,7 @@ const PlayerFragment = ({player}: {player:WasmPlayer}) => {
                     privateKey.publicKey.toString(),
                     gamestate.instance,
                     BigInt((gamestate.get_player_info(privateKey.publicKey.toString())).id),
-                    playpause == "Play" ? "Paused" : "Play",
+                    nextPlayPhase(playpause),
                 ));
                 let tx = new Transaction();
                 tx.add(changeStateIx);
@@ -827,11 +863,21 @@ const PlayerFragment = ({player}: {player:WasmPlayer}) => {
                 connection.sendRawTransaction(tx.serialize(), {skipPreflight: true}).then((sig) => {
                     console.log("Game State Tx: ", sig);
                 })
-            }}>Pause/Play</button>  
+            }}>Vote {nextPlayPhase(playpause)}</button>  
         </div>    
     )
 }
 
+// Phase each vote moves the game towards: Lobby -> Build -> Play <-> Paused
+const nextPlayPhase = (phase:PlayPauseState):PlayPauseState => {
+    switch(phase) {
+        case "Lobby": return "Build";
+        case "Build": return "Play";
+        case "Play": return "Paused";
+        default: return "Play";
+    }
+}
+
 const CreatePlayerFragment = ({setPlayer}: {setPlayer:Function}) => {
     //Game Context 
     const {
@@ -847,21 +893,22 @@ const CreatePlayerFragment = ({setPlayer}: {setPlayer:Function}) => {
 
     // Create Player Function
     const createPlayer = async () => {
-        let player_id = randomU64();
-        let createPlayerIx = ixWasmToJs(dominari.init_player(
-            privateKey.publicKey.toString(),
-            gamestate.instance,
-            player_id,
-            nameref.current?.value!,
-            imageref.current?.value!
-        ));
-        let tx = new Transaction();
-        tx.add(createPlayerIx);
-        tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
-        tx.feePayer = privateKey.publicKey;
-        tx.sign(privateKey);
-        const sig = await connection.sendRawTransaction(tx.serialize(), {skipPreflight: true});
-        await connection.confirmTransaction(sig);
+        const {sig, entityId: player_id} = await sendWithNextEntityId(connection, gamestate, async (player_id) => {
+            let createPlayerIx = ixWasmToJs(dominari.init_player(
+                privateKey.publicKey.toString(),
+                gamestate.instance,
+                player_id,
+                nameref.current?.value!,
+                imageref.current?.value!,
+                undefined // smallest team in team games
+            ));
+            let tx = new Transaction();
+            tx.add(createPlayerIx);
+            tx.recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
+            tx.feePayer = privateKey.publicKey;
+            tx.sign(privateKey);
+            return tx;
+        });
         console.log("TX Confirmed: ", sig);
 
         // TODO: Something here causes an error!
@@ -954,23 +1001,39 @@ const AddTroopModal = ({setShowModal, player, selectedTroopTile}: {setShowModal:
                         
                         console.log(playerID);
                         // Spawn Unit
-                        let ix = ixWasmToJs(dominari.spawn_unit(
-                            privateKey.publicKey.toString(), 
-                            gamestate.instance,
-                            playerID,
-                            randomU64(),
-                            tileID,
-                            selectedUnit.split("-")[0]
-                        ));
-                        
-                        let spawnUnitTx = new Transaction();
-                        let recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
-                        spawnUnitTx.add(ComputeBudgetProgram.setComputeUnitLimit({units:1400000}));
-                        spawnUnitTx.add(ix);
-                        spawnUnitTx.recentBlockhash = recentBlockhash;
-                        spawnUnitTx.feePayer = privateKey.publicKey;
-                        spawnUnitTx.sign(privateKey);
-                        let sig =  await connection.sendRawTransaction(spawnUnitTx.serialize(), {skipPreflight: true});
+                        const {sig} = await sendWithNextEntityId(connection, gamestate, async (unitID) => {
+                            let ix = ixWasmToJs(dominari.spawn_unit(
+                                privateKey.publicKey.toString(), 
+                                gamestate.instance,
+                                playerID,
+                                unitID,
+                                tileID,
+                                selectedUnit.split("-")[0],
+                                gamestate.get_spawn_anchor(playerID, tileID)
+                            ));
+                            
+                            let spawnUnitTx = new Transaction();
+                            let recentBlockhash = (await connection.getLatestBlockhash()).blockhash;
+                            spawnUnitTx.add(ComputeBudgetProgram.setComputeUnitLimit({units:1400000}));
+                            // First unit placed during Build claims the spawn zone it's in
+                            if(gamestate.get_play_phase() == "Build" && gamestate.get_spawn_claim(playerID) === undefined) {
+                                let zones: string[][] = gamestate.get_spawn_zones();
+                                let zone = zones.findIndex((zoneTiles) => zoneTiles.includes(tileID.toString()));
+                                if(zone >= 0) {
+                                    spawnUnitTx.add(ixWasmToJs(dominari.claim_spawn_zone(
+                                        privateKey.publicKey.toString(),
+                                        gamestate.instance,
+                                        playerID,
+                                        zone
+                                    )));
+                                }
+                            }
+                            spawnUnitTx.add(ix);
+                            spawnUnitTx.recentBlockhash = recentBlockhash;
+                            spawnUnitTx.feePayer = privateKey.publicKey;
+                            spawnUnitTx.sign(privateKey);
+                            return spawnUnitTx;
+                        });
                         console.log(`Spawn Unit Tx: ${sig}`);
                         setShowModal(false)
                     }}
@@ -7,12 +7,25 @@ export interface ConfigFileInterface {
 interface GameConfig {
     max_players: number, //u16
     starting_cards: string[] //Vec<Pubkey>    
+    victory_conditions?: any[] //Vec<VictoryCondition>, enums end up as strings or object keys
+    turn_timeout?: bigint //Option<u64>, leave out for real time games
+    distance_metric?: "Chebyshev" | "Manhattan" | "Euclidean" //DistanceMetric, defaults to Chebyshev
+    teams?: number //u8, leave out (or 0) for free for all
+    lobby_duration?: bigint //Option<u64> slots, leave out for no time limit
+    build_duration?: bigint //Option<u64> slots
+    play_duration?: bigint //Option<u64> slots
+    entry_fee?: bigint //u64 lamports each player pays to join, defaults to 0
+    prize_split?: "WinnerTakesAll" | "TopThree" | "Proportional" //PrizeSplit, defaults to WinnerTakesAll
+    counter_attacks?: boolean //surviving defenders strike back, defaults to false
 }
 
 interface MapConfig {
     cost_per_tile: bigint, //u64
     mapmeta: MapMeta,
-    features: Feature[]
+    features?: Feature[],
+    terrain?: TileTerrain[],
+    tiles?: TileOverride[],
+    spawn_zones?: SpawnZone[]
 }
 
 //dominari::component::ComponentMapMeta
@@ -27,10 +40,34 @@ interface Feature {
     feature: string, //blueprint name
 }
 
+//dominari::component::Terrain, tiles left out are Plains
+export type Terrain = "Plains" | "Forest" | "Mountain" | "Water" | "Road";
+
+interface TileTerrain {
+    x: number,
+    y: number,
+    terrain: Terrain,
+}
+
+//dominari-sdk::map_template::MapTemplateTile
+interface TileOverride {
+    x: number,
+    y: number,
+    cost?: bigint, //u64, defaults to cost_per_tile
+    owner?: string, //Pubkey, pre-owned tiles aren't sold by the map
+}
+
+//dominari-sdk::map_template::MapTemplateSpawnZone
+interface SpawnZone {
+    player: number,
+    tiles: [number, number][],
+}
+
 //dominari-sdk::wasm_wrappers::WasmTile
 export interface WasmTile {
     x: number,
     y: number,
+    terrain: Terrain,
     feature: WasmFeature,
     troop: WasmTroop,
 }
@@ -67,7 +104,8 @@ export interface WasmPlayer {
     image: string,
     score: string,
     kills: string,
-    cards: string[]
+    cards: string[],
+    team?: number //u8, only in team games
 }
 
 
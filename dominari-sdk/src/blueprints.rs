@@ -28,6 +28,8 @@ pub struct BlueprintConfig {
     pub active: Option<dominari::component::ComponentActive>,
     pub cost: Option<dominari::component::ComponentCost>,
     pub offchain_metadata: Option<dominari::component::ComponentOffchainMetadata>,
+    pub sight: Option<dominari::component::ComponentSight>,
+    pub portal: Option<dominari::component::ComponentPortal>,
 }
 
 #[wasm_bindgen]
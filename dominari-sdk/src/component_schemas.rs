@@ -77,7 +77,10 @@ impl ComponentIndex {
             "troop_class",
             "active",
             "cost",
-            "offchain_metadata"
+            "offchain_metadata",
+            "terrain",
+            "sight",
+            "portal"
         ];
 
         for url in components_urls {
@@ -114,6 +117,9 @@ impl ComponentIndex {
             active: self.get_component_pubkey(&"active".to_string()),
             cost: self.get_component_pubkey(&"cost".to_string()),
             offchain_metadata: self.get_component_pubkey(&"offchain_metadata".to_string()),
+            terrain: self.get_component_pubkey(&"terrain".to_string()),
+            sight: self.get_component_pubkey(&"sight".to_string()),
+            portal: self.get_component_pubkey(&"portal".to_string()),
         }
     }
 }
\ No newline at end of file
@@ -2,14 +2,21 @@ use anchor_lang::{prelude::*, solana_program::instruction::Instruction, Instruct
 use core_ds::{state::SerializedComponent, constant::SEEDS_ENTITY_PREFIX};
 use core_ds::account::MaxSize;
 use dominari::state::UseFeatureType;
-use dominari::{component::*, constant::{SEEDS_BLUEPRINT, SEEDS_INSTANCEINDEX}, state::GameConfig};
+use dominari::{component::*, constant::{SEEDS_BLUEPRINT, SEEDS_INSTANCEINDEX, SEEDS_TREASURY, SEEDS_COMMITMENT, SEEDS_ESCROW}, state::GameConfig};
+use anchor_lang::solana_program::{hash::hash, sysvar::slot_hashes};
 use wasm_bindgen::{prelude::*, throw_str};
 use std::{str::FromStr, collections::BTreeMap};
 use anchor_lang::system_program::ID as system_program;
 use crate::coreds::get_keys_from_id;
 use crate::wasm_wrappers::GameConfigFile;
 use crate::{component_schemas::ComponentIndex, blueprints::BlueprintConfig};
+use crate::map_template::{MapTemplate, MapTemplateMeta, MapTemplateTerrain};
 
+/**
+ * Entity ids are handed out by the program in order
+ * Builders that make entities take the id they'll get (see GameState::get_next_entity_id) to find the entity account
+ * Ids aren't held for anyone, a tx built for an id someone else took first fails and can be rebuilt off a fresh counter
+ */
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct Dominari {
@@ -193,6 +200,20 @@ impl Dominari {
             });
         }
 
+        if blueprint.sight.is_some() {
+            components.insert(reference.sight, SerializedComponent { 
+                max_size: ComponentSight::get_max_size(), 
+                data:  blueprint.sight.as_ref().unwrap().try_to_vec().unwrap()
+            });
+        }
+
+        if blueprint.portal.is_some() {
+            components.insert(reference.portal, SerializedComponent { 
+                max_size: ComponentPortal::get_max_size(), 
+                data:  blueprint.portal.as_ref().unwrap().try_to_vec().unwrap()
+            });
+        }
+
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
@@ -230,7 +251,7 @@ impl Dominari {
     pub fn create_game_instance(&self, payer:&str, instance: u64, game_config_json: JsValue) -> JsValue {
         let game_config_file:GameConfigFile = serde_wasm_bindgen::from_value(game_config_json).unwrap();
         let starting_cards_keys:Vec<Pubkey> = game_config_file.starting_cards.iter().map(|keystr: &String| {Pubkey::from_str(keystr.as_str()).unwrap()}).collect();
-        let game_config = GameConfig { max_players: game_config_file.max_players, starting_cards: starting_cards_keys };
+        let game_config = GameConfig { max_players: game_config_file.max_players, starting_cards: starting_cards_keys, victory_conditions: game_config_file.victory_conditions, turn_timeout: game_config_file.turn_timeout, distance_metric: game_config_file.distance_metric, teams: game_config_file.teams, lobby_duration: game_config_file.lobby_duration, build_duration: game_config_file.build_duration, play_duration: game_config_file.play_duration, entry_fee: game_config_file.entry_fee, prize_split: game_config_file.prize_split, counter_attacks: game_config_file.counter_attacks };
 
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
@@ -248,6 +269,16 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
+        let treasury = Pubkey::find_program_address(&[
+            SEEDS_TREASURY,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
         let registry_config = Pubkey::find_program_address(&[
             registry::constant::SEEDS_REGISTRYSIGNER,
         ], &registry::id()).0;
@@ -268,6 +299,8 @@ impl Dominari {
                 system_program,
                 config,
                 instance_index,
+                treasury,
+                escrow,
                 registry_config,
                 registry_program: registry::id(),
                 ab_registration,
@@ -285,65 +318,71 @@ impl Dominari {
 
     pub fn init_map(&self, payer:&str, instance:u64, entity_id:u64, max_x:u8, max_y:u8) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
-        let config = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER
-        ], &self.program_id).0;
-        
-        let registry_instance = Pubkey::find_program_address(&[
-            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
-            registry::id().to_bytes().as_ref(),
-            instance.to_be_bytes().as_ref()
-        ], &core_ds::id()).0;
-
-        let instance_index = Pubkey::find_program_address(&[
-            SEEDS_INSTANCEINDEX,
-            registry_instance.to_bytes().as_ref(),
-        ], &self.program_id).0;
-
-        let registry_config = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_REGISTRYSIGNER,
-        ], &registry::id()).0;
-
-        let ab_signer = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER,
-        ], &self.program_id).0;
+        serde_wasm_bindgen::to_value(&self.get_init_map_ix(payer, instance, entity_id, max_x, max_y)).unwrap()
+    }
 
-        let ab_registration = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
-            ab_signer.to_bytes().as_ref()
-        ], &registry::id()).0;
+    /**
+     * terrain_str is one of Plains, Forest, Mountain, Water, Road
+     * owner pre-assigns the tile to someone other than the payer
+     */
+    pub fn init_tile(&self, payer:&str, instance:u64, entity_id:u64, x:u8, y:u8, cost:u64, terrain_str:String, owner:Option<String>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let terrain = terrain_from_str(&terrain_str);
+        let owner = owner.map(|o| Pubkey::from_str(o.as_str()).unwrap());
+        serde_wasm_bindgen::to_value(&self.get_init_tile_ix(payer, instance, entity_id, x, y, cost, terrain, owner)).unwrap()
+    }
 
-        let map_entity = Pubkey::find_program_address(&[
-            SEEDS_ENTITY_PREFIX,
-            entity_id.to_be_bytes().as_ref(),
-            registry_instance.to_bytes().as_ref(),
-        ], &core_ds::id()).0;
+    /**
+     * Inits a width x height rectangle of tiles starting at (x, y), row by row
+     * terrain_json is a list of terrain names in the same order, or empty for all Plains
+     */
+    pub fn init_tiles(&self, payer:&str, instance:u64, entity_ids:Vec<u64>, x:u8, y:u8, width:u8, height:u8, cost:u64, terrain_json:JsValue) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let terrain_strs:Vec<String> = serde_wasm_bindgen::from_value(terrain_json).unwrap();
+        let terrain:Vec<Terrain> = terrain_strs.iter().map(|t| terrain_from_str(t)).collect();
+        serde_wasm_bindgen::to_value(&self.get_init_tiles_ix(payer, instance, entity_ids, x, y, width, height, cost, terrain)).unwrap()
+    }
 
-        let ix = Instruction {
-            program_id: self.program_id,
-            accounts: dominari::accounts::SystemInitMap {
-                payer,
-                system_program,
-                config,
-                instance_index,
-                registry_config,
-                registry_program: registry::id(),
-                ab_registration,
-                coreds: core_ds::id(),
-                registry_instance,
-                map_entity
-            }.to_account_metas(Some(true)),
-            data: dominari::instruction::SystemInitMap {
-                entity_id,
-                max_x,
-                max_y
-            }.data()
+    /**
+     * Every transaction needed to build a max_x by max_y map, in order: init_map, then the tiles
+     * Each transaction has a single init_tiles instruction with at most tiles_per_ix tiles
+     * Full rows are batched together when they fit, otherwise rows are split up
+     * Map gets first_entity_id, tiles get the ids after it row by row
+     * Transactions have to land in order since the program hands out ids as they come in
+     * terrain_json is a list of terrain names for every tile row by row, or empty for all Plains
+     * Same planner as plan_map_template, for a map with nothing but terrain on it
+     */
+    pub fn plan_map(&self, payer:&str, instance:u64, first_entity_id:u64, max_x:u8, max_y:u8, cost:u64, tiles_per_ix:u8, terrain_json:JsValue) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let terrain_strs:Vec<String> = serde_wasm_bindgen::from_value(terrain_json).unwrap();
+        if terrain_strs.len() != 0 && terrain_strs.len() != max_x as usize * max_y as usize {
+            throw_str("Need terrain for every tile!");
+        }
+        let terrain = terrain_strs.iter().enumerate().map(|(idx, t)| MapTemplateTerrain {
+            x: (idx % max_x as usize) as u8,
+            y: (idx / max_x as usize) as u8,
+            terrain: terrain_from_str(t)
+        }).collect();
+
+        let template = MapTemplate {
+            cost_per_tile: cost,
+            mapmeta: MapTemplateMeta { max_x, max_y },
+            features: vec![],
+            terrain,
+            tiles: vec![],
+            spawn_zones: vec![],
         };
+        let plan = self.get_map_plan(payer, instance, first_entity_id, &template, tiles_per_ix);
 
-        serde_wasm_bindgen::to_value(&ix).unwrap()
+        let mut txs: Vec<Vec<Instruction>> = vec![plan.map];
+        txs.extend(plan.tiles);
+        serde_wasm_bindgen::to_value(&txs).unwrap()
     }
 
-    pub fn init_tile(&self, payer:&str, instance:u64, entity_id:u64, x:u8, y:u8, cost:u64) -> JsValue {
+    /**
+     * team is only for team games, leave it out to join the smallest team
+     */
+    pub fn init_player(&self, payer:&str, instance:u64, entity_id:u64, name: String, image: String, team: Option<u8>) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
@@ -360,64 +399,8 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
-        let registry_config = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_REGISTRYSIGNER,
-        ], &registry::id()).0;
-
-        let ab_signer = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER,
-        ], &self.program_id).0;
-
-        let ab_registration = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
-            ab_signer.to_bytes().as_ref()
-        ], &registry::id()).0;
-
-        let tile_entity = Pubkey::find_program_address(&[
-            SEEDS_ENTITY_PREFIX,
-            entity_id.to_be_bytes().as_ref(),
-            registry_instance.to_bytes().as_ref()
-        ], &core_ds::id()).0;
-
-        let ix = Instruction {
-            program_id: self.program_id,
-            accounts: dominari::accounts::SystemInitTile {
-                payer,
-                system_program,
-                config,
-                instance_index,
-                registry_config,
-                registry_program: registry::id(),
-                ab_registration,
-                coreds: core_ds::id(),
-                registry_instance,
-                tile_entity
-            }.to_account_metas(Some(true)),
-            data: dominari::instruction::SystemInitTile {
-                entity_id,
-                x,
-                y,
-                cost
-            }.data()
-        };
-
-        serde_wasm_bindgen::to_value(&ix).unwrap()
-    }
-
-    pub fn init_player(&self, payer:&str, instance:u64, entity_id:u64, name: String, image: String) -> JsValue {
-        let payer = Pubkey::from_str(payer).unwrap();
-        let config = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER
-        ], &self.program_id).0;
-        
-        let registry_instance = Pubkey::find_program_address(&[
-            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
-            registry::id().to_bytes().as_ref(),
-            instance.to_be_bytes().as_ref()
-        ], &core_ds::id()).0;
-
-        let instance_index = Pubkey::find_program_address(&[
-            SEEDS_INSTANCEINDEX,
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
@@ -447,6 +430,7 @@ impl Dominari {
                 system_program,
                 config,
                 instance_index,
+                escrow,
                 registry_config,
                 ab_registration,
                 registry_program: registry::id(),
@@ -455,16 +439,20 @@ impl Dominari {
                 player_entity,
             }.to_account_metas(Some(true)),
             data: dominari::instruction::SystemInitPlayer {
-                entity_id,
                 name,
                 image,
+                team,
             }.data()
         };
 
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-    pub fn spawn_unit(&self, payer:&str, instance:u64, player_id: u64, unit_id:u64, tile_id:u64, blueprint: &str) -> JsValue {
+    /**
+     * anchor_id is the owned Tile or Feature next to tile_id when spawning next to territory during Play
+     * Use GameState::get_spawn_anchor to find it
+     */
+    pub fn spawn_unit(&self, payer:&str, instance:u64, player_id: u64, unit_id:u64, tile_id:u64, blueprint: &str, anchor_id: Option<u64>) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
@@ -481,6 +469,11 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
+        let treasury = Pubkey::find_program_address(&[
+            SEEDS_TREASURY,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
         let registry_config = Pubkey::find_program_address(&[
             registry::constant::SEEDS_REGISTRYSIGNER,
         ], &registry::id()).0;
@@ -502,31 +495,38 @@ impl Dominari {
 
         let unit = get_keys_from_id(registry_instance, vec![unit_id])[0];
 
+        let mut accounts = dominari::accounts::SpawnUnit {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            registry_config,
+            ab_registration,
+            registry_program: registry::id(),
+            coreds: core_ds::id(),
+            registry_instance,
+            unit_blueprint,
+            treasury,
+            player,
+            tile,
+            unit,
+        }.to_account_metas(Some(true));
+        if let Some(anchor_id) = anchor_id {
+            accounts.push(AccountMeta::new_readonly(get_keys_from_id(registry_instance, vec![anchor_id])[0], false));
+        }
+
         let ix = Instruction {
             program_id: self.program_id,
-            accounts: dominari::accounts::SpawnUnit {
-                payer,
-                system_program,
-                config,
-                instance_index,
-                registry_config,
-                ab_registration,
-                registry_program: registry::id(),
-                coreds: core_ds::id(),
-                registry_instance,
-                unit_blueprint,
-                player,
-                tile,
-                unit,
-            }.to_account_metas(Some(true)),
-            data: dominari::instruction::SpawnUnit {
-                unit_id,
-            }.data()
+            accounts,
+            data: dominari::instruction::SpawnUnit {}.data()
         };
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-    pub fn move_unit(&self, payer:&str, instance:u64, unit_id:u64, from_tile_id:u64, to_tile_id:u64) -> JsValue {
+    /**
+     * path_ids are the tiles (and friendly occupants) between From and To, use GameState::get_move_path to find them
+     */
+    pub fn move_unit(&self, payer:&str, instance:u64, unit_id:u64, from_tile_id:u64, to_tile_id:u64, path_ids:Vec<u64>) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
@@ -560,23 +560,27 @@ impl Dominari {
         let from = get_keys_from_id(registry_instance, vec![from_tile_id])[0];
         let to = get_keys_from_id(registry_instance, vec![to_tile_id])[0];
 
+        let mut accounts = dominari::accounts::MoveUnit {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            registry_config,
+            ab_registration,
+            registry_program: registry::id(),
+            coreds: core_ds::id(),
+            registry_instance,
+            unit,
+            from,
+            to
+        }.to_account_metas(Some(true));
+        for step in get_keys_from_id(registry_instance, path_ids) {
+            accounts.push(AccountMeta::new_readonly(step, false));
+        }
 
         let ix = Instruction {
             program_id: self.program_id,
-            accounts: dominari::accounts::MoveUnit {
-                payer,
-                system_program,
-                config,
-                instance_index,
-                registry_config,
-                ab_registration,
-                registry_program: registry::id(),
-                coreds: core_ds::id(),
-                registry_instance,
-                unit,
-                from,
-                to
-            }.to_account_metas(Some(true)),
+            accounts,
             data: dominari::instruction::MoveUnit {
             }.data()
         };
@@ -600,6 +604,11 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
         let registry_config = Pubkey::find_program_address(&[
             registry::constant::SEEDS_REGISTRYSIGNER,
         ], &registry::id()).0;
@@ -632,6 +641,7 @@ impl Dominari {
                 payer,
                 config,
                 instance_index,
+                escrow,
                 registry_config,
                 ab_registration,
                 registry_program: registry::id(),
@@ -647,7 +657,10 @@ impl Dominari {
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-    pub fn init_feature(&self, payer:&str, instance:u64, entity_id:u64, tile_id:u64, blueprint: String) -> JsValue {
+    /**
+     * player_id is the player whose turn it is, not necessarily the payer if they're being skipped
+     */
+    pub fn end_turn(&self, payer:&str, instance:u64, player_id:u64) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
@@ -664,62 +677,75 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
-        let registry_config = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_REGISTRYSIGNER,
-        ], &registry::id()).0;
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
 
-        let ab_signer = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER,
-        ], &self.program_id).0;
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::EndTurn {
+                payer,
+                config,
+                instance_index,
+                registry_instance,
+                player,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::EndTurn {}.data()
+        };
 
-        let ab_registration = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
-            ab_signer.to_bytes().as_ref()
-        ], &registry::id()).0;
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
 
-        let blueprint = Pubkey::from_str(self.get_blueprint_key(blueprint.as_str()).as_str()).unwrap();
-        let tile_entity = Pubkey::find_program_address(&[
-            SEEDS_ENTITY_PREFIX,
-            tile_id.to_be_bytes().as_ref(),
-            registry_instance.to_bytes().as_ref()
+    /**
+     * Authority only, skips the player vote
+     */
+    pub fn force_game_state(&self, payer:&str, instance:u64, game_state_str:String) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
         ], &core_ds::id()).0;
 
-        let feature_entity = Pubkey::find_program_address(&[
-            SEEDS_ENTITY_PREFIX,
-            entity_id.to_be_bytes().as_ref(),
-            registry_instance.to_bytes().as_ref()
-        ], &core_ds::id()).0;
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
 
+        let game_state = match game_state_str.as_str() {
+            "Lobby" => dominari::account::PlayPhase::Lobby,
+            "Build" => dominari::account::PlayPhase::Build,
+            "Play" => dominari::account::PlayPhase::Play,
+            "Paused" => dominari::account::PlayPhase::Paused,
+            "Finished" => dominari::account::PlayPhase::Finished,
+            &_=> throw_str("Invalid game state!")
+        };
 
         let ix = Instruction {
             program_id: self.program_id,
-            accounts: dominari::accounts::SystemInitFeature {
+            accounts: dominari::accounts::ForceGameState {
                 payer,
-                system_program,
-                config,
                 instance_index,
-                registry_config,
-                registry_program: registry::id(),
-                ab_registration,
-                coreds: core_ds::id(),
+                escrow,
                 registry_instance,
-                blueprint,
-                tile_entity,
-                feature_entity,
             }.to_account_metas(Some(true)),
-            data: dominari::instruction::SystemInitFeature {
-                entity_id
+            data: dominari::instruction::ForceGameState {
+                game_state
             }.data()
         };
+
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-    pub fn attack_unit(&self, payer: &str, instance: u64, attacker_id:u64, defender_id:u64, defending_tile_id:u64) -> JsValue {
+    /**
+     * Authority only, while the game is in the Lobby
+     * starting_cards_json is a list of blueprint keys, leave either param out to keep it as is
+     */
+    pub fn update_game_config(&self, payer:&str, instance:u64, max_players:Option<u16>, starting_cards_json:JsValue) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
-        let config = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER
-        ], &self.program_id).0;
-        
         let registry_instance = Pubkey::find_program_address(&[
             core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
             registry::id().to_bytes().as_ref(),
@@ -731,46 +757,35 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
-        let registry_config = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_REGISTRYSIGNER,
-        ], &registry::id()).0;
-
-        let ab_signer = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER,
-        ], &self.program_id).0;
-
-        let ab_registration = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
-            ab_signer.to_bytes().as_ref()
-        ], &registry::id()).0;
-
-        let attacker = get_keys_from_id(registry_instance, vec![attacker_id])[0];
-        let defender = get_keys_from_id(registry_instance, vec![defender_id])[0];
-        let defending_tile = get_keys_from_id(registry_instance, vec![defending_tile_id])[0];
+        let starting_cards:Option<Vec<String>> = serde_wasm_bindgen::from_value(starting_cards_json).unwrap();
+        let starting_cards = starting_cards.map(|cards| {
+            cards.iter().map(|keystr| Pubkey::from_str(keystr.as_str()).unwrap()).collect::<Vec<Pubkey>>()
+        });
 
         let ix = Instruction {
             program_id: self.program_id,
-            accounts: dominari::accounts::AttackTile {
+            accounts: dominari::accounts::UpdateGameConfig {
                 payer,
                 system_program,
-                config,
                 instance_index,
-                registry_config,
-                registry_program: registry::id(),
-                ab_registration,
-                coreds: core_ds::id(),
                 registry_instance,
-                attacker,
-                defender,
-                defending_tile
             }.to_account_metas(Some(true)),
-            data: dominari::instruction::AttackTile {}.data()
+            data: dominari::instruction::UpdateGameConfig {
+                max_players,
+                starting_cards
+            }.data()
         };
+
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-    pub fn use_feature(&self, payer:&str, instance:u64, use_feature_type_str: &str, tile_id:u64, unit_id:u64, feature_id:u64, _target_tile_id:Option<u64>) -> JsValue {
+    /**
+     * Authority only, while the game is in the Lobby
+     * player_wallet gets the entry fee back, use GameState::get_player_wallet to find it
+     */
+    pub fn kick_player(&self, payer:&str, instance:u64, player_id:u64, player_wallet:&str) -> JsValue {
         let payer = Pubkey::from_str(payer).unwrap();
+        let player_wallet = Pubkey::from_str(player_wallet).unwrap();
         let config = Pubkey::find_program_address(&[
             dominari::constant::SEEDS_ABSIGNER
         ], &self.program_id).0;
@@ -786,33 +801,307 @@ impl Dominari {
             registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
-        let registry_config = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_REGISTRYSIGNER,
-        ], &registry::id()).0;
-
-        let ab_signer = Pubkey::find_program_address(&[
-            dominari::constant::SEEDS_ABSIGNER,
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
         ], &self.program_id).0;
 
-        let ab_registration = Pubkey::find_program_address(&[
-            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
-            ab_signer.to_bytes().as_ref()
-        ], &registry::id()).0;
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
 
-        let unit = get_keys_from_id(registry_instance, vec![unit_id])[0];
-        let tile= get_keys_from_id(registry_instance, vec![tile_id])[0];
-        let feature = get_keys_from_id(registry_instance, vec![feature_id])[0];
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::KickPlayer {
+                payer,
+                config,
+                instance_index,
+                escrow,
+                registry_instance,
+                player,
+                player_wallet,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::KickPlayer {}.data()
+        };
+
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Authority only, leave new_authority out to renounce it
+     */
+    pub fn transfer_authority(&self, payer:&str, instance:u64, new_authority:Option<String>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let new_authority = new_authority.map(|keystr| Pubkey::from_str(keystr.as_str()).unwrap());
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::TransferAuthority {
+                payer,
+                instance_index,
+                registry_instance,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::TransferAuthority {
+                new_authority
+            }.data()
+        };
+
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    pub fn init_feature(&self, payer:&str, instance:u64, entity_id:u64, tile_id:u64, blueprint: String) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        serde_wasm_bindgen::to_value(&self.get_init_feature_ix(payer, instance, entity_id, tile_id, blueprint)).unwrap()
+    }
+
+    /**
+     * @param reveal is the secret committed to with commit_randomness
+     * defending_player_id is the defender's player, needed when the game has counter attacks
+     */
+    pub fn attack_unit(&self, payer: &str, instance: u64, attacker_id:u64, attacking_player_id:u64, attacking_tile_id:u64, defender_id:u64, defending_tile_id:u64, defending_player_id:Option<u64>, reveal: Vec<u8>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let attacker = get_keys_from_id(registry_instance, vec![attacker_id])[0];
+        let attacking_player = get_keys_from_id(registry_instance, vec![attacking_player_id])[0];
+        let attacking_tile = get_keys_from_id(registry_instance, vec![attacking_tile_id])[0];
+        let defender = get_keys_from_id(registry_instance, vec![defender_id])[0];
+        let defending_tile = get_keys_from_id(registry_instance, vec![defending_tile_id])[0];
+
+        let commitment = Pubkey::find_program_address(&[
+            SEEDS_COMMITMENT,
+            registry_instance.to_bytes().as_ref(),
+            payer.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let mut accounts = dominari::accounts::AttackTile {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+            commitment,
+            slot_hashes: slot_hashes::ID,
+            attacker,
+            attacking_player,
+            attacking_tile,
+            defender,
+            defending_tile
+        }.to_account_metas(Some(true));
+        if defending_player_id.is_some() {
+            let defending_player = get_keys_from_id(registry_instance, vec![defending_player_id.unwrap()])[0];
+            accounts.push(AccountMeta::new(defending_player, false));
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::AttackTile {
+                reveal: to_reveal(reveal)
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Portals also need the destination tile and the portal feature on it
+     * Loot needs the player entity that owns the unit
+     * Attack needs the defender, the tile it's on (target_tile_id) and the player entity that owns the unit
+     * Loot and Attack also need the secret committed to with commit_randomness
+     */
+    pub fn use_feature(&self, payer:&str, instance:u64, use_feature_type_str: &str, tile_id:u64, unit_id:u64, feature_id:u64, target_tile_id:Option<u64>, target_feature_id:Option<u64>, defender_id:Option<u64>, player_id:Option<u64>, reveal:Option<Vec<u8>>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let unit = get_keys_from_id(registry_instance, vec![unit_id])[0];
+        let tile= get_keys_from_id(registry_instance, vec![tile_id])[0];
+        let feature = get_keys_from_id(registry_instance, vec![feature_id])[0];
 
         let use_feature_type;
 
+        let mut remaining_accounts: Vec<AccountMeta> = vec![];
+        let commitment = Pubkey::find_program_address(&[
+            SEEDS_COMMITMENT,
+            registry_instance.to_bytes().as_ref(),
+            payer.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
         match use_feature_type_str {
             "healer" => {use_feature_type = UseFeatureType::Healer},
+            "portal" => {
+                use_feature_type = UseFeatureType::Portal;
+                if target_tile_id.is_none() || target_feature_id.is_none() {
+                    throw_str("Portals need a target tile and target feature!")
+                }
+                let target_tile = get_keys_from_id(registry_instance, vec![target_tile_id.unwrap()])[0];
+                let target_feature = get_keys_from_id(registry_instance, vec![target_feature_id.unwrap()])[0];
+                remaining_accounts.push(AccountMeta::new(target_tile, false));
+                remaining_accounts.push(AccountMeta::new_readonly(target_feature, false));
+            },
+            "loot" => {
+                use_feature_type = UseFeatureType::Loot;
+                if player_id.is_none() {
+                    throw_str("Looting needs the player that owns the unit!")
+                }
+                let player = get_keys_from_id(registry_instance, vec![player_id.unwrap()])[0];
+                remaining_accounts.push(AccountMeta::new(player, false));
+                remaining_accounts.push(AccountMeta::new(commitment, false));
+                remaining_accounts.push(AccountMeta::new_readonly(slot_hashes::ID, false));
+            },
+            "attack" => {
+                use_feature_type = UseFeatureType::Attack;
+                if target_tile_id.is_none() || defender_id.is_none() {
+                    throw_str("Attacking needs a defender and the tile it's on!")
+                }
+                if player_id.is_none() {
+                    throw_str("Attacking needs the player that owns the unit!")
+                }
+                let player = get_keys_from_id(registry_instance, vec![player_id.unwrap()])[0];
+                let defender = get_keys_from_id(registry_instance, vec![defender_id.unwrap()])[0];
+                let defending_tile = get_keys_from_id(registry_instance, vec![target_tile_id.unwrap()])[0];
+                remaining_accounts.push(AccountMeta::new(player, false));
+                remaining_accounts.push(AccountMeta::new(defender, false));
+                remaining_accounts.push(AccountMeta::new(defending_tile, false));
+                remaining_accounts.push(AccountMeta::new(commitment, false));
+                remaining_accounts.push(AccountMeta::new_readonly(slot_hashes::ID, false));
+            },
             _=>{throw_str("This feature not supported!")}
         }
 
+        let mut accounts = dominari::accounts::UseFeature {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+            unit,
+            tile,
+            feature
+        }.to_account_metas(Some(true));
+        accounts.extend(remaining_accounts);
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::UseFeature {
+                use_feature_type,
+                reveal: reveal.map(to_reveal)
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+
+    pub fn upgrade_feature(&self, payer:&str, instance:u64, tile_id:u64, feature_id:u64) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let treasury = Pubkey::find_program_address(&[
+            SEEDS_TREASURY,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let tile = get_keys_from_id(registry_instance, vec![tile_id])[0];
+        let feature = get_keys_from_id(registry_instance, vec![feature_id])[0];
+
         let ix = Instruction {
             program_id: self.program_id,
-            accounts: dominari::accounts::UseFeature {
+            accounts: dominari::accounts::UpgradeFeature {
                 payer,
                 system_program,
                 config,
@@ -822,18 +1111,805 @@ impl Dominari {
                 ab_registration,
                 coreds: core_ds::id(),
                 registry_instance,
-                unit,
+                treasury,
                 tile,
                 feature
             }.to_account_metas(Some(true)),
-            data: dominari::instruction::UseFeature {
-                use_feature_type
-            }.data()
+            data: dominari::instruction::UpgradeFeature {}.data()
         };
         serde_wasm_bindgen::to_value(&ix).unwrap()
     }
 
-}   
+    /**
+     * @param seller is the current owner's Pubkey when buying a listed tile from another player
+     * @param feature_id is the Feature on the tile if it has one, it changes hands with the tile
+     */
+    pub fn buy_tile(&self, payer:&str, instance:u64, player_id:u64, tile_id:u64, seller:Option<String>, feature_id:Option<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let treasury = Pubkey::find_program_address(&[
+            SEEDS_TREASURY,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+        let tile = get_keys_from_id(registry_instance, vec![tile_id])[0];
+        // Seller isn't used when buying an unclaimed tile
+        let seller = match seller {
+            Some(seller) => Pubkey::from_str(seller.as_str()).unwrap(),
+            None => treasury
+        };
+
+        let mut accounts = dominari::accounts::BuyTile {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            treasury,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+            seller,
+            player,
+            tile
+        }.to_account_metas(Some(true));
+        if let Some(feature_id) = feature_id {
+            accounts.push(AccountMeta::new(get_keys_from_id(registry_instance, vec![feature_id])[0], false));
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::BuyTile {}.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    pub fn list_tile(&self, payer:&str, instance:u64, tile_id:u64, price:u64) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let tile = get_keys_from_id(registry_instance, vec![tile_id])[0];
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::ListTile {
+                payer,
+                config,
+                instance_index,
+                registry_config,
+                registry_program: registry::id(),
+                ab_registration,
+                coreds: core_ds::id(),
+                registry_instance,
+                tile
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::ListTile {
+                price
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Entities are passed as remaining accounts and closed in order
+     * @param benefactor is whoever paid for the entities and gets the rent back, the payer if left out
+     * Owners get back their Troops and Features, the instance creator gets back the Map and Tiles
+     */
+    pub fn reclaim_sol(&self, payer:&str, instance:u64, benefactor:Option<String>, entity_ids:Vec<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let benefactor = match benefactor {
+            Some(benefactor) => Pubkey::from_str(benefactor.as_str()).unwrap(),
+            None => payer
+        };
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let mut accounts = dominari::accounts::ReclaimSol {
+            payer,
+            system_program,
+            benefactor,
+            config,
+            instance_index,
+            escrow,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+        }.to_account_metas(Some(true));
+        for entity in get_keys_from_id(registry_instance, entity_ids) {
+            accounts.push(AccountMeta::new(entity, false));
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::ReclaimSol {}.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * entity_ids come from GameState::get_leave_game_entities
+     */
+    pub fn leave_game(&self, payer:&str, instance:u64, player_id:u64, entity_ids:Vec<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+
+        let mut accounts = dominari::accounts::LeaveGame {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            escrow,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+            player,
+        }.to_account_metas(Some(true));
+        for entity in get_keys_from_id(registry_instance, entity_ids) {
+            accounts.push(AccountMeta::new(entity, false));
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::LeaveGame {}.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Hashes the secret that will later be revealed
+     */
+    pub fn get_commitment(&self, secret: Vec<u8>) -> Vec<u8> {
+        hash(&to_reveal(secret)).to_bytes().to_vec()
+    }
+
+    /**
+     * actor_id is the Unit or Feature that will roll, target_id the entity it rolls against
+     * Attacks commit to the attacker and defender, Loot to the unit and the feature
+     * The reveal has to wait until two slots after the commitment have landed, it rolls off the first one and the newest one when it lands
+     */
+    pub fn commit_randomness(&self, payer:&str, instance:u64, commitment:Vec<u8>, actor_id:u64, target_id:u64) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let commitment_key = Pubkey::find_program_address(&[
+            SEEDS_COMMITMENT,
+            registry_instance.to_bytes().as_ref(),
+            payer.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::CommitRandomness {
+                payer,
+                system_program,
+                commitment: commitment_key,
+                registry_instance,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::CommitRandomness {
+                commitment: to_reveal(commitment),
+                actor: actor_id,
+                target: target_id
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * entity_ids are the owned Tiles (TileControl) the condition is checked against, other conditions don't need any
+     * Use GameState::get_victory_entities to fetch them
+     */
+    pub fn check_victory(&self, payer:&str, instance:u64, player_id:u64, condition_idx:u8, entity_ids:Vec<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+
+        let mut accounts = dominari::accounts::CheckVictory {
+            payer,
+            config,
+            instance_index,
+            escrow,
+            registry_instance,
+            player,
+        }.to_account_metas(Some(true));
+        for entity in get_keys_from_id(registry_instance, entity_ids) {
+            accounts.push(AccountMeta::new_readonly(entity, false));
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::CheckVictory {
+                condition_idx
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Authority only, while the game is in the Lobby
+     * Zones are numbered in the order they're made
+     */
+    pub fn init_spawn_zone(&self, payer:&str, instance:u64, tile_ids:Vec<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        serde_wasm_bindgen::to_value(&self.get_init_spawn_zone_ix(payer, instance, tile_ids)).unwrap()
+    }
+
+    /**
+     * Only during Build, claiming again moves the player to the new zone
+     */
+    pub fn claim_spawn_zone(&self, payer:&str, instance:u64, player_id:u64, zone:u8) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::ClaimSpawnZone {
+                payer,
+                config,
+                instance_index,
+                registry_instance,
+                player,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::ClaimSpawnZone {
+                zone
+            }.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Anyone can call once the phase deadline passes
+     * When Play runs out, player_id and the Tiles they own (entity_ids) claim TileControl for them, use GameState::get_victory_entities to fetch them
+     */
+    pub fn crank_phase(&self, payer:&str, instance:u64, player_id:Option<u64>, entity_ids:Vec<u64>) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let mut accounts = dominari::accounts::CrankPhase {
+            payer,
+            config,
+            instance_index,
+            escrow,
+            registry_instance,
+        }.to_account_metas(Some(true));
+        if let Some(player_id) = player_id {
+            let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+            accounts.push(AccountMeta::new_readonly(player, false));
+            for entity in get_keys_from_id(registry_instance, entity_ids) {
+                accounts.push(AccountMeta::new_readonly(entity, false));
+            }
+        }
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::CrankPhase {}.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+    /**
+     * Only once the game is Finished
+     */
+    pub fn claim_prize(&self, payer:&str, instance:u64, player_id:u64) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let escrow = Pubkey::find_program_address(&[
+            SEEDS_ESCROW,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let player = get_keys_from_id(registry_instance, vec![player_id])[0];
+
+        let accounts = dominari::accounts::ClaimPrize {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            escrow,
+            registry_instance,
+            player,
+        }.to_account_metas(Some(true));
+
+        let ix = Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::ClaimPrize {}.data()
+        };
+        serde_wasm_bindgen::to_value(&ix).unwrap()
+    }
+
+}   
+
+/**
+ * Non WASM Builders, shared by the WASM endpoints that need to batch instructions
+ */
+impl Dominari {
+    pub fn get_init_map_ix(&self, payer:Pubkey, instance:u64, entity_id:u64, max_x:u8, max_y:u8) -> Instruction {
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let map_entity = Pubkey::find_program_address(&[
+            SEEDS_ENTITY_PREFIX,
+            entity_id.to_be_bytes().as_ref(),
+            registry_instance.to_bytes().as_ref(),
+        ], &core_ds::id()).0;
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::SystemInitMap {
+                payer,
+                system_program,
+                config,
+                instance_index,
+                registry_config,
+                registry_program: registry::id(),
+                ab_registration,
+                coreds: core_ds::id(),
+                registry_instance,
+                map_entity
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::SystemInitMap {
+                max_x,
+                max_y
+            }.data()
+        }
+    }
+
+
+    pub fn get_init_tiles_ix(&self, payer:Pubkey, instance:u64, entity_ids:Vec<u64>, x:u8, y:u8, width:u8, height:u8, cost:u64, terrain:Vec<Terrain>) -> Instruction {
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let mut accounts = dominari::accounts::SystemInitTiles {
+            payer,
+            system_program,
+            config,
+            instance_index,
+            registry_config,
+            registry_program: registry::id(),
+            ab_registration,
+            coreds: core_ds::id(),
+            registry_instance,
+        }.to_account_metas(Some(true));
+        for tile_entity in get_keys_from_id(registry_instance, entity_ids) {
+            accounts.push(AccountMeta::new(tile_entity, false));
+        }
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: dominari::instruction::SystemInitTiles {
+                x,
+                y,
+                width,
+                height,
+                cost,
+                terrain
+            }.data()
+        }
+    }
+
+    pub fn get_init_tile_ix(&self, payer:Pubkey, instance:u64, entity_id:u64, x:u8, y:u8, cost:u64, terrain:Terrain, owner:Option<Pubkey>) -> Instruction {
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let tile_entity = Pubkey::find_program_address(&[
+            SEEDS_ENTITY_PREFIX,
+            entity_id.to_be_bytes().as_ref(),
+            registry_instance.to_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::SystemInitTile {
+                payer,
+                system_program,
+                config,
+                instance_index,
+                registry_config,
+                registry_program: registry::id(),
+                ab_registration,
+                coreds: core_ds::id(),
+                registry_instance,
+                tile_entity
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::SystemInitTile {
+                x,
+                y,
+                cost,
+                terrain,
+                owner
+            }.data()
+        }
+    }
+
+    pub fn get_init_feature_ix(&self, payer:Pubkey, instance:u64, entity_id:u64, tile_id:u64, blueprint:String) -> Instruction {
+        let config = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER
+        ], &self.program_id).0;
+        
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let treasury = Pubkey::find_program_address(&[
+            SEEDS_TREASURY,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        let registry_config = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_REGISTRYSIGNER,
+        ], &registry::id()).0;
+
+        let ab_signer = Pubkey::find_program_address(&[
+            dominari::constant::SEEDS_ABSIGNER,
+        ], &self.program_id).0;
+
+        let ab_registration = Pubkey::find_program_address(&[
+            registry::constant::SEEDS_ACTIONBUNDLEREGISTRATION,
+            ab_signer.to_bytes().as_ref()
+        ], &registry::id()).0;
+
+        let blueprint = Pubkey::from_str(self.get_blueprint_key(blueprint.as_str()).as_str()).unwrap();
+        let tile_entity = Pubkey::find_program_address(&[
+            SEEDS_ENTITY_PREFIX,
+            tile_id.to_be_bytes().as_ref(),
+            registry_instance.to_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let feature_entity = Pubkey::find_program_address(&[
+            SEEDS_ENTITY_PREFIX,
+            entity_id.to_be_bytes().as_ref(),
+            registry_instance.to_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::SystemInitFeature {
+                payer,
+                system_program,
+                config,
+                instance_index,
+                registry_config,
+                registry_program: registry::id(),
+                ab_registration,
+                coreds: core_ds::id(),
+                registry_instance,
+                blueprint,
+                treasury,
+                tile_entity,
+                feature_entity,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::SystemInitFeature {}.data()
+        }
+    }
+
+    pub fn get_init_spawn_zone_ix(&self, payer:Pubkey, instance:u64, tile_ids:Vec<u64>) -> Instruction {
+        let registry_instance = Pubkey::find_program_address(&[
+            core_ds::constant::SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::id().to_bytes().as_ref(),
+            instance.to_be_bytes().as_ref()
+        ], &core_ds::id()).0;
+
+        let instance_index = Pubkey::find_program_address(&[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.to_bytes().as_ref(),
+        ], &self.program_id).0;
+
+        Instruction {
+            program_id: self.program_id,
+            accounts: dominari::accounts::SystemInitSpawnZone {
+                payer,
+                system_program,
+                instance_index,
+                registry_instance,
+            }.to_account_metas(Some(true)),
+            data: dominari::instruction::SystemInitSpawnZone {
+                tile_ids
+            }.data()
+        }
+    }
+}
+
+pub fn terrain_from_str(terrain: &str) -> Terrain {
+    match terrain {
+        "Plains" => Terrain::Plains,
+        "Forest" => Terrain::Forest,
+        "Mountain" => Terrain::Mountain,
+        "Water" => Terrain::Water,
+        "Road" => Terrain::Road,
+        &_=> throw_str("Invalid terrain!")
+    }
+}
+
+/**
+ * Secrets and commitments are 32 bytes
+ */
+pub fn to_reveal(bytes: Vec<u8>) -> [u8; 32] {
+    if bytes.len() != 32 {
+        throw_str("Secrets and commitments must be 32 bytes!");
+    }
+    bytes.try_into().unwrap()
+}
 
 /*
 Initialization
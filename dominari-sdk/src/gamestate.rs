@@ -5,6 +5,8 @@ use solana_client_wasm::WasmClient;
 use dominari::account::{InstanceIndex, PlayPhase};
 use core_ds::account::Entity;
 use dominari::component::*;
+use dominari::state::VictoryCondition;
+use dominari::constant::MOVEMENT_SCALE;
 use crate::{component_schemas::ComponentIndex, coreds::{get_registry_instance, get_keys_from_id}, wasm_wrappers::{WasmTile, WasmFeature, WasmTroop, WasmPlayer}, blueprints::BlueprintIndex};
 //use web_sys::console;
 
@@ -149,6 +151,34 @@ impl GameState{
         serde_wasm_bindgen::to_value(&tiles).unwrap()
     }
 
+    /**
+     * Same as get_map but enemy troops out of sight of the player's side are left off their tiles
+     */
+    pub fn get_visible_map(&self, player_id:u64) -> JsValue {
+        if self.index.is_none() {
+            throw_str("Load state first!")
+        }
+        let mut tiles: Vec<WasmTile> = vec![];
+        let observers = self.get_observers(player_id);
+
+        for tile_id in self.index.as_ref().unwrap().tiles.iter() {
+            let mut tile = self.get_tile_info(*tile_id);
+            let hidden = match &tile.troop {
+                Some(troop) => {
+                    let troop_id = u64::from_str(troop.id.as_str()).unwrap();
+                    !self.is_friendly_to(player_id, &troop_id) && !observers.iter().any(|(x, y, sight)| get_distance(*x, *y, tile.x, tile.y) <= *sight)
+                },
+                None => false
+            };
+            if hidden {
+                tile.troop = None;
+            }
+            tiles.push(tile);
+        }
+
+        serde_wasm_bindgen::to_value(&tiles).unwrap()
+    }
+
     /**
      * @param user is Pubkey
      */
@@ -167,20 +197,261 @@ impl GameState{
                 image: stats.image,
                 score: stats.score.to_string(),
                 kills: stats.kills.to_string(),
-                cards: cardnames
+                cards: cardnames,
+                team: self.index.as_ref().unwrap().get_team(player_id.unwrap())
             };
             return serde_wasm_bindgen::to_value(&player).unwrap();
         }
     }
 
     pub fn get_play_phase(&self) -> String {
-        match self.index.as_ref().unwrap().play_phase {
-            PlayPhase::Build => return String::from("Build"),
-            PlayPhase::Lobby => return String::from("Lobby"),
-            PlayPhase::Play => return String::from("Play"),
-            PlayPhase::Paused => return String::from("Paused"),
-            PlayPhase::Finished => return String::from("Finished"),
+        play_phase_to_string(&self.index.as_ref().unwrap().play_phase)
+    }
+
+    /**
+     * Phase the players are currently voting on, if any
+     */
+    pub fn get_proposed_phase(&self) -> Option<String> {
+        self.index.as_ref().unwrap().proposed_phase.as_ref().map(play_phase_to_string)
+    }
+
+    /**
+     * Player ID (u64 as string) whose turn it is, None for real time games
+     */
+    pub fn get_current_player(&self) -> Option<String> {
+        let index = self.index.as_ref().unwrap();
+        if index.config.turn_timeout.is_none() || index.turn == 0 {
+            return None;
+        }
+        Some(index.current_player.to_string())
+    }
+
+    pub fn get_turn(&self) -> u64 {
+        self.index.as_ref().unwrap().turn
+    }
+
+    /**
+     * Id the program will give the next entity made in this instance
+     * Entities made in one transaction get this id and the ones after it, in order
+     * Call update_instance_index first, another player may have made an entity since
+     */
+    pub fn get_next_entity_id(&self) -> u64 {
+        self.index.as_ref().unwrap().next_entity_id
+    }
+
+    /**
+     * Player IDs that have voted for the proposed phase
+     */
+    pub fn get_phase_votes(&self) -> Vec<u64> {
+        self.index.as_ref().unwrap().votes.clone()
+    }
+
+    /**
+     * Cheapest path for a Unit between two tiles within its Movement, in the order move_unit expects
+     * Returns the tiles between From and To, each occupied one followed by its (friendly) Unit
+     */
+    pub fn get_move_path(&self, unit_id:u64, from_tile_id:u64, to_tile_id:u64) -> Vec<u64> {
+        let index = self.index.as_ref().unwrap();
+        let metric = &index.config.distance_metric;
+        let unit_owner = self.get_entity_owner(&unit_id).unwrap();
+        let budget = self.get_entity_range(&unit_id).unwrap().movement as u64 * MOVEMENT_SCALE;
+        let class = self.get_entity_troop_class(&unit_id).unwrap().class;
+
+        let mut tiles: HashMap<(u8, u8), u64> = HashMap::new();
+        for tile_id in index.tiles.iter() {
+            let location = self.get_entity_location(tile_id).unwrap();
+            tiles.insert((location.x, location.y), *tile_id);
+        }
+        let from_location = self.get_entity_location(&from_tile_id).unwrap();
+        let to_location = self.get_entity_location(&to_tile_id).unwrap();
+        let from = (from_location.x, from_location.y);
+        let to = (to_location.x, to_location.y);
+
+        // Dijkstra, maps are small enough to just scan for the cheapest open tile
+        let mut costs: HashMap<(u8, u8), u64> = HashMap::new();
+        let mut previous: HashMap<(u8, u8), (u8, u8)> = HashMap::new();
+        let mut open: Vec<(u8, u8)> = vec![from];
+        costs.insert(from, 0);
+        while open.len() > 0 {
+            let (i, _) = open.iter().enumerate().min_by_key(|(_, node)| costs[node]).unwrap();
+            let node = open.swap_remove(i);
+            if node == to {
+                break;
+            }
+
+            for dx in -1..=1_i16 {
+                for dy in -1..=1_i16 {
+                    let (x, y) = (node.0 as i16 + dx, node.1 as i16 + dy);
+                    if x < 0 || y < 0 || x > u8::MAX as i16 || y > u8::MAX as i16 {
+                        continue;
+                    }
+                    let next = (x as u8, y as u8);
+                    let tile_id = match tiles.get(&next) {
+                        Some(tile_id) => tile_id,
+                        None => continue
+                    };
+                    let terrain = self.get_entity_terrain(tile_id).map_or(Terrain::Plains, |t| t.terrain);
+                    let from_location = ComponentLocation { x: node.0, y: node.1 };
+                    let to_location = ComponentLocation { x: next.0, y: next.1 };
+                    let cost = match dominari::get_step_cost(metric, &from_location, &to_location, &terrain, &class) {
+                        Ok(step) => costs[&node] + step,
+                        Err(_) => continue
+                    };
+                    if cost > budget {
+                        continue;
+                    }
+                    // Can pass through friendly Units, but has to end on an empty tile
+                    if let Some(occupant) = self.get_entity_occupant(tile_id).unwrap().occupant_id {
+                        if next == to || !self.get_entity_owner(&occupant).unwrap().is_friendly(&unit_owner) {
+                            continue;
+                        }
+                    }
+                    if costs.get(&next).map_or(true, |&c| cost < c) {
+                        costs.insert(next, cost);
+                        previous.insert(next, node);
+                        if !open.contains(&next) {
+                            open.push(next);
+                        }
+                    }
+                }
+            }
+        }
+
+        if !previous.contains_key(&to) {
+            throw_str("No path in range!");
+        }
+
+        // Walk back from To, leaving out both ends
+        let mut path: Vec<u64> = vec![];
+        let mut node = previous[&to];
+        while node != from {
+            let tile_id = tiles[&node];
+            if let Some(occupant) = self.get_entity_occupant(&tile_id).unwrap().occupant_id {
+                path.push(occupant);
+            }
+            path.push(tile_id);
+            node = previous[&node];
+        }
+        path.reverse();
+        path
+    }
+
+    /**
+     * Authority can be handed off, or renounced (then this is the default key)
+     */
+    pub fn get_authority(&self) -> String {
+        self.index.as_ref().unwrap().authority.to_string()
+    }
+
+    /**
+     * Player ID (u64 as string) of the winner, if the game has been won
+     */
+    pub fn get_winner(&self) -> Option<String> {
+        self.index.as_ref().unwrap().winner.map(|winner| winner.to_string())
+    }
+
+    /**
+     * Entities check_victory needs for the given condition
+     * The Tiles held by the player's side for TileControl, nothing for the others
+     */
+    pub fn get_victory_entities(&self, player_id:u64, condition_idx:u8) -> Vec<u64> {
+        let index = self.index.as_ref().unwrap();
+        match index.config.victory_conditions.get(condition_idx as usize) {
+            Some(VictoryCondition::LastPlayerStanding) => vec![],
+            Some(VictoryCondition::TileControl { tiles: _, deadline: _ }) => {
+                index.tiles.iter().filter(|tile_id| self.is_friendly_to(player_id, tile_id)).cloned().collect()
+            },
+            Some(VictoryCondition::Score { threshold: _ }) => vec![],
+            None => throw_str("Game doesn't have that victory condition!")
+        }
+    }
+
+    /**
+     * Entities leave_game needs to forfeit (empty in the Lobby)
+     * The player's living Units, each with the Tile under it right after it
+     */
+    pub fn get_leave_game_entities(&self, player_id:u64) -> Vec<u64> {
+        let index = self.index.as_ref().unwrap();
+        if index.play_phase == PlayPhase::Lobby {
+            return vec![];
+        }
+
+        let mut entities = vec![];
+        for unit_id in index.units.iter() {
+            let is_owned = self.get_entity_owner(unit_id).map_or(false, |owner| owner.player == Some(player_id));
+            let is_active = self.get_entity_active(unit_id).map_or(false, |active| active.active);
+            if is_owned && is_active {
+                entities.push(*unit_id);
+                let location = self.get_entity_location(unit_id).unwrap();
+                entities.push(u64::from_str(self.get_tile_id(location.x, location.y).as_str()).unwrap());
+            }
+        }
+        entities
+    }
+
+    /**
+     * Player IDs (u64 as string) on the player's team, including the player
+     * Just the player outside of team games
+     */
+    pub fn get_teammates(&self, player_id:u64) -> Vec<String> {
+        let index = self.index.as_ref().unwrap();
+        let team = index.get_team(player_id);
+        if team.is_none() {
+            return vec![player_id.to_string()];
+        }
+        index.players.iter().filter(|&&p| index.get_team(p) == team).map(|p| p.to_string()).collect()
+    }
+
+    /**
+     * Tile IDs (u64 as string) in each spawn zone, in zone order
+     */
+    pub fn get_spawn_zones(&self) -> JsValue {
+        let zones: Vec<Vec<String>> = self.index.as_ref().unwrap().spawn_zones.iter().map(|zone| {
+            zone.iter().map(|tile_id| tile_id.to_string()).collect()
+        }).collect();
+        serde_wasm_bindgen::to_value(&zones).unwrap()
+    }
+
+    pub fn get_spawn_claim(&self, player_id:u64) -> Option<u8> {
+        self.index.as_ref().unwrap().spawn_claims.get(&player_id).cloned()
+    }
+
+    /**
+     * Owned Tile or active Feature next to the tile that spawn_unit needs during Play
+     * None if the tile is already the player's side's territory, or if nothing they own is next to it
+     */
+    pub fn get_spawn_anchor(&self, player_id:u64, tile_id:u64) -> Option<u64> {
+        let index = self.index.as_ref().unwrap();
+        if self.is_friendly_to(player_id, &tile_id) {
+            return None;
         }
+        let location = self.get_entity_location(&tile_id).unwrap();
+        index.tiles.iter().chain(index.features.iter()).find(|&&anchor_id| {
+            let anchor_location = self.get_entity_location(&anchor_id).unwrap();
+            anchor_location.x.abs_diff(location.x) <= 1 && anchor_location.y.abs_diff(location.y) <= 1 && self.is_friendly_to(player_id, &anchor_id)
+                && self.get_entity_active(&anchor_id).map_or(true, |active| active.active)
+        }).cloned()
+    }
+
+    /**
+     * Slot the current phase runs out, if it has a duration
+     */
+    pub fn get_phase_deadline(&self) -> Option<u64> {
+        self.index.as_ref().unwrap().phase_deadline
+    }
+
+    /**
+     * Wallet (as string) that joined as the player, kick_player refunds the entry fee to it
+     */
+    pub fn get_player_wallet(&self, player_id:u64) -> Option<String> {
+        self.get_entity_player_stats(&player_id).map(|stats| stats.key.to_string())
+    }
+
+    /**
+     * Player IDs (u64 as string) that left the game after the Lobby
+     */
+    pub fn get_forfeited_players(&self) -> Vec<String> {
+        self.index.as_ref().unwrap().forfeited.iter().map(|id| id.to_string()).collect()
     }
 }
 
@@ -225,10 +496,12 @@ impl GameState {
         let location = self.get_entity_location(&tile_id).unwrap();
         let feature = self.get_entity_feature(&tile_id).unwrap().feature_id;
         let troop = self.get_entity_occupant(&tile_id).unwrap().occupant_id;
+        let terrain = self.get_entity_terrain(&tile_id).map_or(Terrain::Plains, |t| t.terrain);
 
         let mut tile = WasmTile {
             x: location.x,
             y: location.y,
+            terrain,
             feature: None,
             troop: None,
         };
@@ -280,6 +553,52 @@ impl GameState {
         tile
     }
 
+    /**
+     * Whether the entity belongs to the player or one of their teammates
+     */
+    pub fn is_friendly_to(&self, player_id:u64, entity_id:&u64) -> bool {
+        let index = self.index.as_ref().unwrap();
+        let side = ComponentOwner {
+            owner: None,
+            player: Some(player_id),
+            team: index.get_team(player_id),
+        };
+        match self.get_entity_owner(entity_id) {
+            Some(owner) => owner.is_friendly(&side),
+            None => false
+        }
+    }
+
+    /**
+     * (x, y, sight) for every living unit and every feature on a tile held by the player's side
+     * Entities without a Sight component see as far as they can attack, or just the tiles around them
+     */
+    pub fn get_observers(&self, player_id:u64) -> Vec<(u8, u8, u8)> {
+        let index = self.index.as_ref().unwrap();
+        let mut observers = vec![];
+        for tile_id in index.tiles.iter() {
+            let location = self.get_entity_location(tile_id).unwrap();
+            if let Some(troop_id) = self.get_entity_occupant(tile_id).unwrap().occupant_id {
+                if self.is_friendly_to(player_id, &troop_id) {
+                    observers.push((location.x, location.y, self.get_sight(&troop_id)));
+                }
+            }
+            if let Some(feature_id) = self.get_entity_feature(tile_id).unwrap().feature_id {
+                if self.is_friendly_to(player_id, tile_id) {
+                    observers.push((location.x, location.y, self.get_sight(&feature_id)));
+                }
+            }
+        }
+        observers
+    }
+
+    pub fn get_sight(&self, entity_id:&u64) -> u8 {
+        match self.get_entity_sight(entity_id) {
+            Some(sight) => sight.sight,
+            None => self.get_entity_range(entity_id).map_or(1, |range| range.attack_range.max(1))
+        }
+    }
+
     pub fn get_player(&self, player: Pubkey) -> Option<u64> {
         if self.index.is_none() {
             return None;
@@ -420,6 +739,18 @@ impl GameState {
         if sc.is_none() { return None };
         Some(ComponentOffchainMetadata::try_from_slice(&sc.unwrap().data.as_slice()).unwrap())
     }
+    pub fn get_entity_terrain(&self, entity_id: &u64) -> Option<ComponentTerrain> {
+        let serialized_components = &self.entities.get(&entity_id).unwrap().components;
+        let sc = serialized_components.get(&self.component_index.get_relevant_component_keys().terrain.key());
+        if sc.is_none() { return None };
+        Some(ComponentTerrain::try_from_slice(&sc.unwrap().data.as_slice()).unwrap())
+    }
+    pub fn get_entity_sight(&self, entity_id: &u64) -> Option<ComponentSight> {
+        let serialized_components = &self.entities.get(&entity_id).unwrap().components;
+        let sc = serialized_components.get(&self.component_index.get_relevant_component_keys().sight.key());
+        if sc.is_none() { return None };
+        Some(ComponentSight::try_from_slice(&sc.unwrap().data.as_slice()).unwrap())
+    }
 }
 
 pub async fn fetch_account<T: AccountDeserialize>(client: &WasmClient, pubkey: &Pubkey) -> Result<T> {
@@ -445,4 +776,21 @@ pub async fn fetch_accounts<T: AccountDeserialize>(client: &WasmClient, pubkeys:
 pub async fn deserialize_account<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
     let result = T::try_deserialize(&mut data).map_err(Into::into);
     return result;
+}
+
+/**
+ * Same rounding the program uses for attack range
+ */
+pub fn get_distance(from_x:u8, from_y:u8, to_x:u8, to_y:u8) -> u8 {
+    ((from_x as f64 - to_x as f64).powf(2_f64) + (from_y as f64 - to_y as f64).powf(2_f64)).sqrt() as u8
+}
+
+pub fn play_phase_to_string(phase: &PlayPhase) -> String {
+    match phase {
+        PlayPhase::Build => return String::from("Build"),
+        PlayPhase::Lobby => return String::from("Lobby"),
+        PlayPhase::Play => return String::from("Play"),
+        PlayPhase::Paused => return String::from("Paused"),
+        PlayPhase::Finished => return String::from("Finished"),
+    }
 }
\ No newline at end of file
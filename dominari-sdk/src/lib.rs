@@ -4,5 +4,6 @@ pub mod component_schemas;
 pub mod blueprints;
 pub mod wasm_wrappers;
 pub mod gamestate;
+pub mod map_template;
 pub mod coreds;
 extern crate console_error_panic_hook;
@@ -0,0 +1,440 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use dominari::component::Terrain;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+use wasm_bindgen::{prelude::*, throw_str};
+use crate::dominari::Dominari;
+
+/**
+ * Map Template TOML, the [map] table of a game config file
+ * Tiles left out of terrain and tiles use the defaults
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateFile {
+    pub map: MapTemplate,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplate {
+    pub cost_per_tile: u64,
+    pub mapmeta: MapTemplateMeta,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<MapTemplateFeature>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub terrain: Vec<MapTemplateTerrain>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tiles: Vec<MapTemplateTile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawn_zones: Vec<MapTemplateSpawnZone>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateMeta {
+    pub max_x: u8,
+    pub max_y: u8,
+}
+
+/**
+ * Feature is a blueprint name
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateFeature {
+    pub x: u8,
+    pub y: u8,
+    pub feature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateTerrain {
+    pub x: u8,
+    pub y: u8,
+    pub terrain: Terrain,
+}
+
+/**
+ * Overrides the cost of a tile, or pre-assigns it to an owner (Pubkey as string)
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateTile {
+    pub x: u8,
+    pub y: u8,
+    pub cost: Option<u64>,
+    pub owner: Option<String>,
+}
+
+/**
+ * Tiles a player's starting units go on, zones are made in player order
+ */
+#[derive(Serialize, Deserialize)]
+pub struct MapTemplateSpawnZone {
+    pub player: u8,
+    pub tiles: Vec<(u8, u8)>,
+}
+
+/**
+ * Every transaction needed to build a map from a template
+ * Transactions have to land in the order they're listed, entity ids are handed out by the program as they come in
+ */
+#[derive(Serialize)]
+pub struct MapPlan {
+    pub map: Vec<Instruction>,
+    pub tiles: Vec<Vec<Instruction>>,
+    pub features: Vec<Vec<Instruction>>,
+    pub spawn_zones: Vec<Vec<Instruction>>,
+}
+
+impl MapTemplate {
+    pub fn from_toml(template: &str) -> Self {
+        match toml::from_str::<MapTemplateFile>(template) {
+            Ok(file) => file.map,
+            Err(e) => throw_str(format!("Invalid map template: {}", e).as_str())
+        }
+    }
+
+    pub fn to_toml(self) -> String {
+        toml::to_string(&MapTemplateFile { map: self }).unwrap()
+    }
+
+    /**
+     * Tiles get the ids after the map, row by row
+     */
+    pub fn get_tile_id(&self, first_entity_id:u64, x:u8, y:u8) -> u64 {
+        first_entity_id + 1 + (y as u64 * self.mapmeta.max_x as u64) + x as u64
+    }
+
+    /**
+     * Features get the ids after the tiles, in template order
+     */
+    pub fn get_feature_id(&self, first_entity_id:u64, idx:usize) -> u64 {
+        first_entity_id + 1 + (self.mapmeta.max_x as u64 * self.mapmeta.max_y as u64) + idx as u64
+    }
+
+    fn get_terrain(&self) -> BTreeMap<(u8, u8), Terrain> {
+        self.terrain.iter().map(|t| ((t.x, t.y), t.terrain.clone())).collect()
+    }
+
+    fn in_bounds(&self, x:u8, y:u8) -> bool {
+        x < self.mapmeta.max_x && y < self.mapmeta.max_y
+    }
+}
+
+#[wasm_bindgen]
+impl Dominari {
+    /**
+     * Turns a map template TOML into a MapPlan
+     * Whole game config files work too, only the [map] table is read
+     * first_entity_id is GameState::get_next_entity_id before the map is made
+     * Every id in the plan is predicted up front, if anything else makes an entity while it lands the rest of the plan fails
+     * Tiles are batched at most tiles_per_ix to a transaction, tiles with a custom cost or owner get their own instruction
+     */
+    pub fn plan_map_template(&self, payer:&str, instance:u64, first_entity_id:u64, template_toml:String, tiles_per_ix:u8) -> JsValue {
+        let payer = Pubkey::from_str(payer).unwrap();
+        let template = MapTemplate::from_toml(template_toml.as_str());
+        serde_wasm_bindgen::to_value(&self.get_map_plan(payer, instance, first_entity_id, &template, tiles_per_ix)).unwrap()
+    }
+}
+
+impl Dominari {
+    pub fn get_map_plan(&self, payer:Pubkey, instance:u64, first_entity_id:u64, template:&MapTemplate, tiles_per_ix:u8) -> MapPlan {
+        let max_x = template.mapmeta.max_x;
+        let max_y = template.mapmeta.max_y;
+        if tiles_per_ix == 0 {
+            throw_str("Need at least one tile per instruction!");
+        }
+
+        let terrain = template.get_terrain();
+        let mut custom_tiles: BTreeMap<(u8, u8), &MapTemplateTile> = BTreeMap::new();
+        for tile in template.tiles.iter() {
+            if !template.in_bounds(tile.x, tile.y) {
+                throw_str(format!("Tile ({}, {}) is off the map!", tile.x, tile.y).as_str());
+            }
+            custom_tiles.insert((tile.x, tile.y), tile);
+        }
+        let get_tile_terrain = |x:u8, y:u8| terrain.get(&(x, y)).cloned().unwrap_or(Terrain::Plains);
+
+        let mut tiles: Vec<Vec<Instruction>> = vec![];
+
+        // Rectangles of full rows if they fit, otherwise segments of a single row
+        // Bands with custom tiles are split up around them
+        let band_height = if tiles_per_ix >= max_x { tiles_per_ix / max_x } else { 1 };
+        let mut y = 0;
+        while y < max_y {
+            let rect_height = band_height.min(max_y - y);
+            let band_has_custom = custom_tiles.keys().any(|(_, ty)| *ty >= y && *ty < y + rect_height);
+            if rect_height > 1 && !band_has_custom {
+                let mut entity_ids = vec![];
+                let mut rect_terrain = vec![];
+                for dy in 0..rect_height {
+                    for x in 0..max_x {
+                        entity_ids.push(template.get_tile_id(first_entity_id, x, y + dy));
+                        rect_terrain.push(get_tile_terrain(x, y + dy));
+                    }
+                }
+                tiles.push(vec![self.get_init_tiles_ix(payer, instance, entity_ids, 0, y, max_x, rect_height, template.cost_per_tile, rect_terrain)]);
+                y += rect_height;
+                continue;
+            }
+
+            for row in y..(y + rect_height) {
+                let mut x = 0;
+                while x < max_x {
+                    if let Some(tile) = custom_tiles.get(&(x, row)) {
+                        let owner = tile.owner.as_ref().map(|o| Pubkey::from_str(o.as_str()).unwrap());
+                        tiles.push(vec![self.get_init_tile_ix(
+                            payer,
+                            instance,
+                            template.get_tile_id(first_entity_id, x, row),
+                            x,
+                            row,
+                            tile.cost.unwrap_or(template.cost_per_tile),
+                            get_tile_terrain(x, row),
+                            owner
+                        )]);
+                        x += 1;
+                        continue;
+                    }
+
+                    // Run of default tiles up to the next custom tile
+                    let mut run_width = 0;
+                    while x + run_width < max_x && run_width < tiles_per_ix && !custom_tiles.contains_key(&(x + run_width, row)) {
+                        run_width += 1;
+                    }
+                    let entity_ids = (x..x + run_width).map(|tx| template.get_tile_id(first_entity_id, tx, row)).collect();
+                    let run_terrain = (x..x + run_width).map(|tx| get_tile_terrain(tx, row)).collect();
+                    tiles.push(vec![self.get_init_tiles_ix(payer, instance, entity_ids, x, row, run_width, 1, template.cost_per_tile, run_terrain)]);
+                    x += run_width;
+                }
+            }
+            y += rect_height;
+        }
+
+        // Features are built by the tile owner, so they can't go on tiles pre-assigned to someone else
+        let mut features: Vec<Vec<Instruction>> = vec![];
+        for (idx, feature) in template.features.iter().enumerate() {
+            if !template.in_bounds(feature.x, feature.y) {
+                throw_str(format!("Feature at ({}, {}) is off the map!", feature.x, feature.y).as_str());
+            }
+            if let Some(tile) = custom_tiles.get(&(feature.x, feature.y)) {
+                if tile.owner.is_some() && tile.owner != Some(payer.to_string()) {
+                    throw_str(format!("Feature at ({}, {}) is on a pre-owned tile!", feature.x, feature.y).as_str());
+                }
+            }
+            features.push(vec![self.get_init_feature_ix(
+                payer,
+                instance,
+                template.get_feature_id(first_entity_id, idx),
+                template.get_tile_id(first_entity_id, feature.x, feature.y),
+                feature.feature.clone()
+            )]);
+        }
+
+        // On chain zones are numbered in the order they're made, which lines up with player order
+        let mut zones: Vec<&MapTemplateSpawnZone> = template.spawn_zones.iter().collect();
+        zones.sort_by_key(|zone| zone.player);
+        let mut spawn_zones: Vec<Vec<Instruction>> = vec![];
+        for zone in zones {
+            for (x, y) in zone.tiles.iter() {
+                if !template.in_bounds(*x, *y) {
+                    throw_str(format!("Spawn zone tile ({}, {}) is off the map!", x, y).as_str());
+                }
+            }
+            let tile_ids = zone.tiles.iter().map(|(x, y)| template.get_tile_id(first_entity_id, *x, *y)).collect();
+            spawn_zones.push(vec![self.get_init_spawn_zone_ix(payer, instance, tile_ids)]);
+        }
+
+        MapPlan {
+            map: vec![self.get_init_map_ix(payer, instance, first_entity_id, max_x, max_y)],
+            tiles,
+            features,
+            spawn_zones,
+        }
+    }
+}
+
+/**
+ * Generates a map template that's the same for every player
+ * 2 player maps are point symmetric around the center, 4 player maps are mirrored on both axes
+ * Each player gets a spawn zone of Plains in their corner, features are mirrored the same way as terrain
+ * feature_names are blueprint names, feature_count of them are placed per player
+ */
+#[wasm_bindgen]
+pub fn generate_map_template(seed:u64, max_x:u8, max_y:u8, players:u8, cost_per_tile:u64, feature_names:Vec<String>, feature_count:u8) -> String {
+    if players != 2 && players != 4 {
+        throw_str("Generated maps support 2 or 4 players!");
+    }
+    if max_x < 2 || max_y < 2 {
+        throw_str("Map needs to be at least 2x2!");
+    }
+    if feature_count > 0 && feature_names.len() == 0 {
+        throw_str("Need feature names to place features!");
+    }
+
+    let zone_size = (max_x.min(max_y) / 4).max(1);
+    let mut spawn_zones = vec![];
+    for player in 0..players {
+        let mut tiles = vec![];
+        for dy in 0..zone_size {
+            for dx in 0..zone_size {
+                let x = if player == 1 || player == 3 { max_x - 1 - dx } else { dx };
+                let y = if player == 1 || player == 2 { max_y - 1 - dy } else { dy };
+                tiles.push((x, y));
+            }
+        }
+        spawn_zones.push(MapTemplateSpawnZone { player, tiles });
+    }
+    let spawn_tiles: BTreeSet<(u8, u8)> = spawn_zones.iter().flat_map(|zone| zone.tiles.clone()).collect();
+
+    // Every symmetric copy of a tile rolls with the same canonical coordinate
+    let mut terrain = vec![];
+    for y in 0..max_y {
+        for x in 0..max_x {
+            if spawn_tiles.contains(&(x, y)) {
+                continue;
+            }
+            let (cx, cy) = canonical_tile(x, y, max_x, max_y, players);
+            let roll = mix_seed(seed, &[0, cx as u64, cy as u64]) % 100;
+            let tile_terrain = match roll {
+                0..=54 => continue,
+                55..=74 => Terrain::Forest,
+                75..=84 => Terrain::Mountain,
+                85..=94 => Terrain::Road,
+                _ => Terrain::Water,
+            };
+            terrain.push(MapTemplateTerrain { x, y, terrain: tile_terrain });
+        }
+    }
+    let water: BTreeSet<(u8, u8)> = terrain.iter().filter(|t| t.terrain == Terrain::Water).map(|t| (t.x, t.y)).collect();
+
+    let mut features = vec![];
+    let mut taken: BTreeSet<(u8, u8)> = BTreeSet::new();
+    let mut attempt = 0;
+    let mut placed = 0;
+    // Bail out on small maps that run out of room
+    while placed < feature_count && attempt < (feature_count as u64 * 16) {
+        let x = (mix_seed(seed, &[1, attempt, 0]) % max_x as u64) as u8;
+        let y = (mix_seed(seed, &[1, attempt, 1]) % max_y as u64) as u8;
+        attempt += 1;
+
+        let copies = symmetric_tiles(x, y, max_x, max_y, players);
+        if copies.iter().any(|tile| spawn_tiles.contains(tile) || water.contains(tile) || taken.contains(tile)) {
+            continue;
+        }
+        let feature = feature_names[placed as usize % feature_names.len()].clone();
+        for (fx, fy) in copies {
+            taken.insert((fx, fy));
+            features.push(MapTemplateFeature { x: fx, y: fy, feature: feature.clone() });
+        }
+        placed += 1;
+    }
+
+    MapTemplate {
+        cost_per_tile,
+        mapmeta: MapTemplateMeta { max_x, max_y },
+        features,
+        terrain,
+        tiles: vec![],
+        spawn_zones,
+    }.to_toml()
+}
+
+/**
+ * Every copy of a tile under the map's symmetry, without duplicates
+ */
+fn symmetric_tiles(x:u8, y:u8, max_x:u8, max_y:u8, players:u8) -> Vec<(u8, u8)> {
+    let mut tiles = vec![(x, y), (max_x - 1 - x, max_y - 1 - y)];
+    if players == 4 {
+        tiles.push((max_x - 1 - x, y));
+        tiles.push((x, max_y - 1 - y));
+    }
+    let tiles: BTreeSet<(u8, u8)> = tiles.into_iter().collect();
+    tiles.into_iter().collect()
+}
+
+fn canonical_tile(x:u8, y:u8, max_x:u8, max_y:u8, players:u8) -> (u8, u8) {
+    // BTreeSet orders by x then y, which is all that matters for picking the same copy every time
+    symmetric_tiles(x, y, max_x, max_y, players)[0]
+}
+
+/**
+ * SplitMix64 over the seed and some inputs
+ */
+fn mix_seed(seed:u64, inputs:&[u64]) -> u64 {
+    let mut state = seed;
+    for input in inputs {
+        state = splitmix64(state ^ *input);
+    }
+    state
+}
+
+fn splitmix64(state:u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(seed:u64, max_x:u8, max_y:u8, players:u8) -> MapTemplate {
+        let features = vec!["Healer".to_string(), "Portal".to_string()];
+        MapTemplate::from_toml(generate_map_template(seed, max_x, max_y, players, 10, features, 3).as_str())
+    }
+
+    fn assert_symmetric(template:&MapTemplate, players:u8) {
+        let max_x = template.mapmeta.max_x;
+        let max_y = template.mapmeta.max_y;
+        let terrain = template.get_terrain();
+        let features: BTreeMap<(u8, u8), String> = template.features.iter().map(|f| ((f.x, f.y), f.feature.clone())).collect();
+        for y in 0..max_y {
+            for x in 0..max_x {
+                for (cx, cy) in symmetric_tiles(x, y, max_x, max_y, players) {
+                    assert_eq!(terrain.get(&(x, y)), terrain.get(&(cx, cy)), "terrain at ({}, {}) and ({}, {})", x, y, cx, cy);
+                    assert_eq!(features.get(&(x, y)), features.get(&(cx, cy)), "feature at ({}, {}) and ({}, {})", x, y, cx, cy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generate_map_template_two_players_is_point_symmetric() {
+        for seed in 0..8 {
+            assert_symmetric(&generate(seed, 12, 9, 2), 2);
+        }
+    }
+
+    #[test]
+    fn generate_map_template_four_players_is_mirrored_on_both_axes() {
+        for seed in 0..8 {
+            assert_symmetric(&generate(seed, 10, 10, 4), 4);
+        }
+    }
+
+    #[test]
+    fn generate_map_template_gives_every_player_a_matching_plains_zone() {
+        let template = generate(7, 12, 12, 4);
+        let terrain = template.get_terrain();
+        assert_eq!(template.spawn_zones.len(), 4);
+        for zone in template.spawn_zones.iter() {
+            assert_eq!(zone.tiles.len(), template.spawn_zones[0].tiles.len());
+            for tile in zone.tiles.iter() {
+                assert!(terrain.get(tile).is_none());
+            }
+        }
+        // Player 1's zone is player 0's rotated around the center
+        for (x, y) in template.spawn_zones[0].tiles.iter() {
+            assert!(template.spawn_zones[1].tiles.contains(&(11 - x, 11 - y)));
+        }
+    }
+
+    #[test]
+    fn generate_map_template_same_seed_same_map() {
+        let features = vec!["Healer".to_string()];
+        let a = generate_map_template(42, 8, 8, 2, 10, features.clone(), 2);
+        let b = generate_map_template(42, 8, 8, 2, 10, features, 2);
+        assert_eq!(a, b);
+    }
+}
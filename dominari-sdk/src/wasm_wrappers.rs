@@ -1,4 +1,5 @@
-use dominari::component::TroopClass;
+use dominari::component::{TroopClass, Terrain};
+use dominari::state::{VictoryCondition, DistanceMetric, PrizeSplit};
 use serde::{Serialize, Deserialize};
 
 /**
@@ -11,12 +12,29 @@ use serde::{Serialize, Deserialize};
 pub struct GameConfigFile {
     pub max_players:u16,
     pub starting_cards: Vec<String>,
+    #[serde(default)]
+    pub victory_conditions: Vec<VictoryCondition>,
+    pub turn_timeout: Option<u64>,
+    #[serde(default)]
+    pub distance_metric: DistanceMetric,
+    #[serde(default)]
+    pub teams: u8,
+    pub lobby_duration: Option<u64>,
+    pub build_duration: Option<u64>,
+    pub play_duration: Option<u64>,
+    #[serde(default)]
+    pub entry_fee: u64,
+    #[serde(default)]
+    pub prize_split: PrizeSplit,
+    #[serde(default)]
+    pub counter_attacks: bool,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct WasmTile {
     pub x: u8,
     pub y: u8,
+    pub terrain: Terrain,
     pub feature: Option<WasmFeature>,
     pub troop: Option<WasmTroop>,
 }
@@ -64,7 +82,8 @@ pub struct WasmPlayer {
     pub score: String, //u64 as String
     pub kills: String, //u64 as String
     //Blueprint Names rather than Pubkey
-    pub cards: Vec<String>
+    pub cards: Vec<String>,
+    pub team: Option<u8>,
 }
 
 #[derive(Deserialize, Debug)]
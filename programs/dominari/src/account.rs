@@ -2,7 +2,7 @@ use anchor_lang::prelude::*;
 use core_ds::state::SerializedComponent;
 use std::collections::BTreeMap;
 use core_ds::account::MaxSize;
-use crate::{state::*, constant::STRING_MAX_SIZE};
+use crate::{state::*, constant::STRING_MAX_SIZE, error::DominariError, component::ComponentOwner};
 
 #[account]
 pub struct Config {
@@ -38,17 +38,343 @@ impl MaxSize for Blueprint {
  * Then realloc+ on entity spawn
  */
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[account]
 pub struct InstanceIndex {
     pub authority: Pubkey,
+    // Whoever made the instance, Tiles nobody has bought yet stay in their name even if authority moves
+    pub creator: Pubkey,
     pub config: GameConfig,
     pub map: u64,
     pub tiles: Vec<u64>,
     pub features: Vec<u64>,
     pub units: Vec<u64>,
     pub players: Vec<u64>,
-    pub play_phase: PlayPhase
+    // Players that left after the Lobby, they're out of players but their entities stick around
+    pub forfeited: Vec<u64>,
+    // Team each player is on, empty outside of team games
+    pub player_teams: BTreeMap<u64, u8>,
+    // Tiles in each spawn zone, and the zone each player claimed during Build
+    pub spawn_zones: Vec<Vec<u64>>,
+    pub spawn_claims: BTreeMap<u64, u8>,
+    // Living Units and score of each player still in the game, so checks across every player don't need every entity passed in
+    pub player_units: BTreeMap<u64, u16>,
+    pub player_scores: BTreeMap<u64, u64>,
+    // Players that have spawned at least one Unit
+    pub deployed: Vec<u64>,
+    pub play_phase: PlayPhase,
+    pub winner: Option<u64>,
+    // Phase the players are voting to move to, and who's voted for it
+    pub proposed_phase: Option<PlayPhase>,
+    pub votes: Vec<u64>,
+    // Turn based games only
+    pub current_player: u64,
+    pub turn: u64,
+    pub turn_start: u64,
+    // Slot the current phase started, and when it runs out if the config gives it a duration
+    pub phase_start: u64,
+    pub phase_deadline: Option<u64>,
+    // Game was Finished before it got to Play, entry fees get refunded
+    pub abandoned: bool,
+    // Entity ids are handed out in order by the program, starting at FIRST_ENTITY_ID
+    pub next_entity_id: u64,
+}
+
+impl InstanceIndex {
+    /**
+     * Moves the game to the new phase and throws out any pending vote
+     * Finishing a game that never got to Play abandons it
+     * Turn based games start their first turn (or restart the current one after a pause) when moving to Play
+     * Pausing stops the phase clock, so resuming pushes the deadline back by however long the pause was
+     */
+    pub fn set_play_phase(&mut self, phase: PlayPhase, slot: u64) {
+        self.phase_deadline = match (&self.play_phase, &phase) {
+            (_, PlayPhase::Paused) => self.phase_deadline,
+            (PlayPhase::Paused, PlayPhase::Play) => self.phase_deadline.map(|deadline| deadline + (slot - self.phase_start)),
+            _ => self.config.get_phase_duration(&phase).map(|duration| slot + duration)
+        };
+        self.phase_start = slot;
+        if phase == PlayPhase::Finished && (self.play_phase == PlayPhase::Lobby || self.play_phase == PlayPhase::Build) {
+            self.abandoned = true;
+        }
+        if phase == PlayPhase::Play && self.config.turn_timeout.is_some() {
+            if self.turn == 0 && self.players.len() > 0 {
+                self.turn = 1;
+                self.current_player = self.players[0];
+            }
+            self.turn_start = slot;
+        }
+        self.play_phase = phase;
+        self.proposed_phase = None;
+        self.votes = vec![];
+    }
+
+    /**
+     * Real time games let anyone act whenever, turn based games only let the current player act
+     */
+    pub fn check_turn(&self, player: Option<u64>) -> Result<()> {
+        if self.config.turn_timeout.is_some() && player != Some(self.current_player) {
+            return err!(DominariError::NotYourTurn)
+        }
+        Ok(())
+    }
+
+    /**
+     * Unclaimed Tiles are held by the creator and sold by the map, anything else belongs to a player or was pre-owned
+     */
+    pub fn is_unclaimed(&self, tile_owner: &ComponentOwner) -> bool {
+        tile_owner.player.is_none() && tile_owner.owner == Some(self.creator)
+    }
+
+    /**
+     * Removes a player from the game and any vote they're part of
+     * If it was their turn, the next player goes
+     */
+    pub fn remove_player(&mut self, player: u64, slot: u64) {
+        let was_current = self.config.turn_timeout.is_some() && self.turn > 0 && self.current_player == player;
+        if was_current && self.players.len() > 1 {
+            self.next_turn(slot);
+        }
+        self.players.retain(|&p| p != player);
+        self.votes.retain(|&p| p != player);
+        self.player_teams.remove(&player);
+        self.spawn_claims.remove(&player);
+        self.player_units.remove(&player);
+        self.player_scores.remove(&player);
+        self.deployed.retain(|&p| p != player);
+    }
+
+    /**
+     * Moves the game to the proposed phase if the votes in are already enough
+     * Checked when a player leaves, since fewer players need fewer votes
+     */
+    pub fn settle_vote(&mut self, slot: u64) -> Option<PlayPhase> {
+        let proposed = self.proposed_phase.clone()?;
+        let votes_needed = self.play_phase.votes_needed(&proposed, self.players.len())?;
+        if self.votes.is_empty() || self.votes.len() < votes_needed {
+            return None
+        }
+        self.set_play_phase(proposed.clone(), slot);
+        Some(proposed)
+    }
+
+    pub fn add_living_unit(&mut self, player: u64) {
+        if let Some(count) = self.player_units.get_mut(&player) {
+            *count += 1;
+        }
+    }
+
+    pub fn remove_living_unit(&mut self, player: u64) {
+        if let Some(count) = self.player_units.get_mut(&player) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    pub fn get_living_units(&self, player: u64) -> u16 {
+        self.player_units.get(&player).cloned().unwrap_or(0)
+    }
+
+    pub fn add_score(&mut self, player: u64, value: u64) {
+        if let Some(score) = self.player_scores.get_mut(&player) {
+            *score += value;
+        }
+    }
+
+    /**
+     * Every player still in the game and their score, in join order
+     */
+    pub fn get_scores(&self) -> Vec<(u64, u64)> {
+        self.players.iter().map(|p| (*p, self.player_scores.get(p).cloned().unwrap_or(0))).collect()
+    }
+
+    /**
+     * A player is out once they've spawned and lost every Unit, or never had a card to spawn with
+     * Players that haven't spawned yet still hold their starting cards, since looting takes a Unit
+     */
+    pub fn is_eliminated(&self, player: u64) -> bool {
+        self.get_living_units(player) == 0 && (self.deployed.contains(&player) || self.config.starting_cards.is_empty())
+    }
+
+    /**
+     * Whether the player's side (them, or their team) is the only one left that isn't eliminated
+     */
+    pub fn last_side_standing(&self, player: u64) -> bool {
+        let team = self.get_team(player);
+        let mut standing = false;
+        for &p in self.players.iter() {
+            if self.is_eliminated(p) {
+                continue;
+            }
+            if p != player && (team.is_none() || self.get_team(p) != team) {
+                return false
+            }
+            standing = true;
+        }
+        standing
+    }
+
+    /**
+     * Whether the Tile is in the spawn zone the player claimed
+     */
+    pub fn in_spawn_zone(&self, player: u64, tile: u64) -> bool {
+        match self.spawn_claims.get(&player) {
+            Some(zone) => self.spawn_zones[*zone as usize].contains(&tile),
+            None => false
+        }
+    }
+
+    pub fn get_team(&self, player: u64) -> Option<u8> {
+        self.player_teams.get(&player).cloned()
+    }
+
+    /**
+     * Team a joining player goes on, the smallest team if they don't pick one
+     */
+    pub fn assign_team(&self, team: Option<u8>) -> Result<Option<u8>> {
+        if self.config.teams == 0 {
+            if team.is_some() {
+                return err!(DominariError::InvalidTeam)
+            }
+            return Ok(None)
+        }
+        match team {
+            Some(team) if team < self.config.teams => Ok(Some(team)),
+            Some(_) => err!(DominariError::InvalidTeam),
+            None => Ok((0..self.config.teams).min_by_key(|t| self.player_teams.values().filter(|pt| *pt == t).count()))
+        }
+    }
+
+    /**
+     * True once everyone left in the game is on the same side
+     */
+    pub fn one_side_left(&self) -> bool {
+        if self.players.len() <= 1 {
+            return true
+        }
+        let team = self.get_team(self.players[0]);
+        team.is_some() && self.players.iter().all(|&p| self.get_team(p) == team)
+    }
+
+    /**
+     * Reserves count entity ids and returns the first one
+     * Clients predict ids off next_entity_id, so ids always go in order
+     * Nothing is held for a client between reading the counter and landing a tx
+     * If another entity lands first the registry's seeds check fails the stale tx without changing anything,
+     * so clients re-read next_entity_id and rebuild the tx
+     * Map plans predict ids for many txs at once and can't be rebuilt half way, so maps are made before the lobby is shared
+     */
+    pub fn allocate_entity_ids(&mut self, count: u64) -> u64 {
+        let first = self.next_entity_id;
+        self.next_entity_id += count;
+        first
+    }
+
+    /**
+     * Phase the game moves to once the current phase's deadline passes
+     * A Lobby that never filled up is abandoned and goes straight to Finished
+     */
+    pub fn get_expired_phase(&self) -> Option<PlayPhase> {
+        match self.play_phase {
+            PlayPhase::Lobby if self.players.len() < 2 => Some(PlayPhase::Finished),
+            PlayPhase::Lobby => Some(PlayPhase::Build),
+            PlayPhase::Build => Some(PlayPhase::Play),
+            PlayPhase::Play => Some(PlayPhase::Finished),
+            _ => None
+        }
+    }
+
+    /**
+     * Passes the turn to the next player in join order
+     */
+    pub fn next_turn(&mut self, slot: u64) {
+        let idx = self.players.iter().position(|&p| p == self.current_player).unwrap_or(0);
+        self.current_player = self.players[(idx + 1) % self.players.len()];
+        self.turn += 1;
+        self.turn_start = slot;
+    }
+}
+
+/**
+ * Holds the lamports paid into an instance (blueprint costs, tiles, etc)
+ * Init during Create Game Instance
+ */
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug)]
+#[account]
+pub struct InstanceTreasury {
+    pub instance: u64,
+}
+
+impl MaxSize for InstanceTreasury {
+    fn get_max_size() -> u64 {
+        return 8;
+    }
+}
+
+/**
+ * Holds the entry fees paid into an instance until they're paid out as prizes or refunded
+ * Only the program can move lamports out of it
+ * Init during Create Game Instance
+ */
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug)]
+#[account]
+pub struct InstanceEscrow {
+    pub instance: u64,
+    // Entry fees paid by players still in the game, prizes are split from this
+    pub pot: u64,
+    // Prizes and refunds paid out of the pot so far
+    pub paid: u64,
+    // Players that have been paid their prize or refund
+    pub claimed: Vec<u64>,
+    // Every player's prize, saved as soon as the game is Finished so claims don't depend on who's closed their Player
+    // Room for it (and for claimed) is paid for by each player as they join
+    pub payouts: Option<Vec<(u64, u64)>>,
+}
+
+impl MaxSize for InstanceEscrow {
+    fn get_max_size() -> u64 {
+        return 8+8+8+4+1+4;
+    }
+}
+
+impl InstanceEscrow {
+    /**
+     * What the player is still owed once the game is Finished
+     * Their saved prize, or their entry fee back if the game was abandoned with them still in it
+     */
+    pub fn owed(&self, player_id: u64, index: &InstanceIndex) -> u64 {
+        if self.claimed.contains(&player_id) {
+            return 0
+        }
+        if index.abandoned {
+            return if index.players.contains(&player_id) { index.config.entry_fee } else { 0 }
+        }
+        match &self.payouts {
+            Some(payouts) => payouts.iter().find(|(id, _)| *id == player_id).map_or(0, |(_, amount)| *amount),
+            None => 0
+        }
+    }
+}
+
+/**
+ * A player's hashed secret for their next roll, and the entities it's for
+ * Zeroed out once it's been revealed
+ */
+#[account]
+pub struct RandomnessCommitment {
+    pub player: Pubkey,
+    pub instance: u64,
+    pub commitment: [u8; 32],
+    pub slot: u64,
+    pub actor: u64,  // Unit or Feature doing the rolling
+    pub target: u64, // Entity it's rolling against
+}
+
+impl MaxSize for RandomnessCommitment {
+    fn get_max_size() -> u64 {
+        return 32+8+32+8+8+8;
+    }
 }
 
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
@@ -61,6 +387,30 @@ pub enum PlayPhase {
     Finished
 }
 
+impl Default for PlayPhase {
+    fn default() -> Self {
+        PlayPhase::Lobby
+    }
+}
+
+impl PlayPhase {
+    /**
+     * Lobby -> Build -> Play <-> Paused -> Finished
+     * Returns None if the transition isn't allowed, otherwise how many player votes it needs
+     * Moving forward needs every player to be ready, pausing and unpausing needs a majority
+     */
+    pub fn votes_needed(&self, to: &PlayPhase, player_count: usize) -> Option<usize> {
+        match (self, to) {
+            (PlayPhase::Lobby, PlayPhase::Build) => Some(player_count),
+            (PlayPhase::Build, PlayPhase::Play) => Some(player_count),
+            (PlayPhase::Play, PlayPhase::Paused) => Some(player_count / 2 + 1),
+            (PlayPhase::Paused, PlayPhase::Play) => Some(player_count / 2 + 1),
+            (PlayPhase::Paused, PlayPhase::Finished) => Some(player_count),
+            _ => None
+        }
+    }
+}
+
 /**
  * DOES NOT INCLUDE GAME CONFIG SIZE
  * To fetch that, use the get_max_size() function on the config object
@@ -68,6 +418,143 @@ pub enum PlayPhase {
  */
 impl MaxSize for InstanceIndex {
     fn get_max_size() -> u64 {
-        return 32+8+4+4+4+4+2;
+        return 32+32+8+4+4+4+4+4+4+4+4+4+4+4+2+9+2+4+8+8+8+8+9+8+1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::FIRST_ENTITY_ID;
+
+    #[test]
+    fn allocate_entity_ids_hands_out_ids_in_order() {
+        let mut index = InstanceIndex { next_entity_id: FIRST_ENTITY_ID, ..Default::default() };
+        assert_eq!(index.allocate_entity_ids(1), FIRST_ENTITY_ID);
+        assert_eq!(index.allocate_entity_ids(1), FIRST_ENTITY_ID + 1);
+        assert_eq!(index.next_entity_id, FIRST_ENTITY_ID + 2);
+    }
+
+    #[test]
+    fn allocate_entity_ids_reserves_whole_batches() {
+        let mut index = InstanceIndex { next_entity_id: FIRST_ENTITY_ID, ..Default::default() };
+        let map = index.allocate_entity_ids(1);
+        let tiles = index.allocate_entity_ids(8);
+        let player = index.allocate_entity_ids(1);
+        assert_eq!(tiles, map + 1);
+        assert_eq!(player, tiles + 8);
+        assert_eq!(index.next_entity_id, player + 1);
+    }
+
+    #[test]
+    fn votes_needed_moving_forward_needs_everyone() {
+        assert_eq!(PlayPhase::Lobby.votes_needed(&PlayPhase::Build, 4), Some(4));
+        assert_eq!(PlayPhase::Build.votes_needed(&PlayPhase::Play, 4), Some(4));
+        assert_eq!(PlayPhase::Paused.votes_needed(&PlayPhase::Finished, 3), Some(3));
+    }
+
+    #[test]
+    fn votes_needed_pausing_needs_a_majority() {
+        assert_eq!(PlayPhase::Play.votes_needed(&PlayPhase::Paused, 1), Some(1));
+        assert_eq!(PlayPhase::Play.votes_needed(&PlayPhase::Paused, 4), Some(3));
+        assert_eq!(PlayPhase::Play.votes_needed(&PlayPhase::Paused, 5), Some(3));
+        assert_eq!(PlayPhase::Paused.votes_needed(&PlayPhase::Play, 2), Some(2));
+    }
+
+    #[test]
+    fn votes_needed_rejects_transitions_off_the_table() {
+        assert_eq!(PlayPhase::Lobby.votes_needed(&PlayPhase::Play, 2), None);
+        assert_eq!(PlayPhase::Build.votes_needed(&PlayPhase::Lobby, 2), None);
+        assert_eq!(PlayPhase::Play.votes_needed(&PlayPhase::Finished, 2), None);
+        assert_eq!(PlayPhase::Play.votes_needed(&PlayPhase::Play, 2), None);
+        assert_eq!(PlayPhase::Finished.votes_needed(&PlayPhase::Lobby, 2), None);
+    }
+
+    // Two players holding a starting card, units maps each player to their living Units
+    fn two_player_index(units: &[(u64, u16)], deployed: Vec<u64>) -> InstanceIndex {
+        InstanceIndex {
+            config: GameConfig { starting_cards: vec![Pubkey::default()], ..Default::default() },
+            players: vec![1, 2],
+            player_units: units.iter().cloned().collect(),
+            deployed,
+            next_entity_id: FIRST_ENTITY_ID,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn last_side_standing_waits_for_players_that_havent_spawned() {
+        let index = two_player_index(&[(1, 1), (2, 0)], vec![1]);
+        assert!(!index.is_eliminated(2));
+        assert!(!index.last_side_standing(1));
+        assert!(!index.last_side_standing(2));
+    }
+
+    #[test]
+    fn last_side_standing_once_the_other_side_lost_every_unit() {
+        let index = two_player_index(&[(1, 1), (2, 0)], vec![1, 2]);
+        assert!(index.is_eliminated(2));
+        assert!(index.last_side_standing(1));
+        assert!(!index.last_side_standing(2));
+
+        // Still holding cards doesn't help once they've spawned and lost everything
+        let index = two_player_index(&[(1, 0), (2, 0)], vec![1]);
+        assert!(index.is_eliminated(1));
+        assert!(index.last_side_standing(2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn last_side_standing_without_cards_to_spawn() {
+        let mut index = two_player_index(&[(1, 1), (2, 0)], vec![1]);
+        index.config.starting_cards = vec![];
+        assert!(index.is_eliminated(2));
+        assert!(index.last_side_standing(1));
+    }
+
+    #[test]
+    fn last_side_standing_counts_teammates() {
+        let mut index = two_player_index(&[(1, 0), (2, 1)], vec![1, 2]);
+        index.player_teams = BTreeMap::from([(1, 0), (2, 0)]);
+        assert!(index.last_side_standing(1));
+        assert!(index.last_side_standing(2));
+    }
+
+    fn escrow(pot: u64, payouts: Option<Vec<(u64, u64)>>) -> InstanceEscrow {
+        InstanceEscrow { instance: 0, pot, paid: 0, claimed: vec![], payouts }
+    }
+
+    #[test]
+    fn set_play_phase_abandons_games_finished_before_play() {
+        let mut index = two_player_index(&[], vec![]);
+        index.play_phase = PlayPhase::Build;
+        index.set_play_phase(PlayPhase::Finished, 10);
+        assert!(index.abandoned);
+
+        let mut index = two_player_index(&[], vec![]);
+        index.play_phase = PlayPhase::Paused;
+        index.set_play_phase(PlayPhase::Finished, 10);
+        assert!(!index.abandoned);
+    }
+
+    #[test]
+    fn owed_pays_a_saved_prize_once() {
+        let index = two_player_index(&[], vec![]);
+        let mut escrow = escrow(300, Some(vec![(1, 200), (2, 100)]));
+        assert_eq!(escrow.owed(1, &index), 200);
+        assert_eq!(escrow.owed(3, &index), 0);
+        escrow.claimed.push(1);
+        assert_eq!(escrow.owed(1, &index), 0);
+        assert_eq!(escrow.owed(2, &index), 100);
+    }
+
+    #[test]
+    fn owed_refunds_players_still_in_an_abandoned_game() {
+        let mut index = two_player_index(&[], vec![]);
+        index.config.entry_fee = 50;
+        index.abandoned = true;
+        index.players = vec![1];
+        let escrow = escrow(50, None);
+        assert_eq!(escrow.owed(1, &index), 50);
+        assert_eq!(escrow.owed(2, &index), 0);
+    }
+}
@@ -69,12 +69,22 @@ impl MaxSize for ComponentFeature {
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct ComponentOwner{
     pub owner: Option<Pubkey>,    // Keypair for Tile Owner
-    pub player: Option<u64>    // Entity ID for Tile Owner's Player
+    pub player: Option<u64>,    // Entity ID for Tile Owner's Player
+    pub team: Option<u8>,    // Team of the Owner's Player, None outside of team games
 }
 
 impl MaxSize for ComponentOwner {
     fn get_max_size() -> u64 {
-        return 1+32+1+8
+        return 1+32+1+8+1+1
+    }
+}
+
+impl ComponentOwner {
+    /**
+     * Same player, or teammates in a team game
+     */
+    pub fn is_friendly(&self, other: &ComponentOwner) -> bool {
+        self.player == other.player || (self.team.is_some() && self.team == other.team)
     }
 }
 
@@ -285,3 +295,116 @@ impl MaxSize for ComponentOffchainMetadata {
         return STRING_MAX_SIZE*2 //can be 2 times regular string for long url links
     }
 }
+
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentTerrain{
+    pub terrain: Terrain,
+}
+
+impl MaxSize for ComponentTerrain {
+    fn get_max_size() -> u64 {
+        return 1
+    }
+}
+
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentSight{
+    pub sight: u8,
+}
+
+impl MaxSize for ComponentSight {
+    fn get_max_size() -> u64 {
+        return 1
+    }
+}
+
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentPortal{
+    pub network: u64, // Portals on the same network with the same owner are linked
+}
+
+impl MaxSize for ComponentPortal {
+    fn get_max_size() -> u64 {
+        return 8
+    }
+}
+
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq)]
+pub enum Terrain {
+    Plains,
+    Forest,
+    Mountain,
+    Water,
+    Road,
+}
+
+impl Terrain {
+    /**
+     * Percent of a normal step it costs to move onto this terrain, None if the class can't enter it
+     * Aircraft fly over everything
+     */
+    pub fn movement_cost(&self, class: &TroopClass) -> Option<u64> {
+        match (self, class) {
+            (_, TroopClass::Aircraft) => Some(100),
+            (Terrain::Plains, _) => Some(100),
+            (Terrain::Road, _) => Some(50),
+            (Terrain::Forest, TroopClass::Infantry) => Some(150),
+            (Terrain::Forest, TroopClass::Armor) => Some(200),
+            (Terrain::Mountain, TroopClass::Infantry) => Some(200),
+            (Terrain::Mountain, TroopClass::Armor) => None,
+            (Terrain::Water, _) => None,
+        }
+    }
+
+    /**
+     * Percent of incoming damage ground units on this terrain shrug off
+     */
+    pub fn defense_bonus(&self) -> u64 {
+        match self {
+            Terrain::Forest => 20,
+            Terrain::Mountain => 40,
+            _ => 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movement_cost_by_terrain_and_class() {
+        assert_eq!(Terrain::Plains.movement_cost(&TroopClass::Armor), Some(100));
+        assert_eq!(Terrain::Road.movement_cost(&TroopClass::Infantry), Some(50));
+        assert_eq!(Terrain::Forest.movement_cost(&TroopClass::Infantry), Some(150));
+        assert_eq!(Terrain::Forest.movement_cost(&TroopClass::Armor), Some(200));
+        assert_eq!(Terrain::Mountain.movement_cost(&TroopClass::Infantry), Some(200));
+    }
+
+    #[test]
+    fn movement_cost_blocks_impassable_terrain() {
+        assert_eq!(Terrain::Mountain.movement_cost(&TroopClass::Armor), None);
+        assert_eq!(Terrain::Water.movement_cost(&TroopClass::Infantry), None);
+        assert_eq!(Terrain::Water.movement_cost(&TroopClass::Armor), None);
+    }
+
+    #[test]
+    fn movement_cost_aircraft_fly_over_everything() {
+        for terrain in [Terrain::Plains, Terrain::Forest, Terrain::Mountain, Terrain::Water, Terrain::Road] {
+            assert_eq!(terrain.movement_cost(&TroopClass::Aircraft), Some(100));
+        }
+    }
+
+    #[test]
+    fn defense_bonus_only_forest_and_mountain_give_cover() {
+        assert_eq!(Terrain::Plains.defense_bonus(), 0);
+        assert_eq!(Terrain::Forest.defense_bonus(), 20);
+        assert_eq!(Terrain::Mountain.defense_bonus(), 40);
+        assert_eq!(Terrain::Water.defense_bonus(), 0);
+        assert_eq!(Terrain::Road.defense_bonus(), 0);
+    }
+}
@@ -6,4 +6,13 @@ pub const DROP_TABLE_MAX_SIZE:u64 = 32;
 pub const SEEDS_ABSIGNER:&[u8;9] = b"ab_signer";
 pub const SEEDS_INSTANCEINDEX:&[u8;14] = b"instance_index";
 pub const SEEDS_BLUEPRINT:&[u8;9] = b"blueprint";
-pub const ENTITY_ID_SIZE: usize = 8;
\ No newline at end of file
+pub const SEEDS_TREASURY:&[u8;8] = b"treasury";
+pub const SEEDS_COMMITMENT:&[u8;10] = b"commitment";
+pub const SEEDS_ESCROW:&[u8;6] = b"escrow";
+pub const ENTITY_ID_SIZE: usize = 8;
+pub const FIRST_ENTITY_ID: u64 = 1;
+// Room a player takes up in the Instance Index: their id, their vote, their team, their spawn zone, their living Units, their score and whether they've spawned
+pub const PLAYER_INDEX_SIZE: usize = (ENTITY_ID_SIZE * 7) + 2 + 2 + 8;
+// Room a player takes up in the Escrow: their id and prize in payouts, and their id in claimed
+pub const PAYOUT_SIZE: usize = ENTITY_ID_SIZE + 8 + ENTITY_ID_SIZE;
+pub const MOVEMENT_SCALE: u64 = 100;
@@ -146,6 +146,47 @@ pub struct SystemInitTile<'info> {
 
 }
 
+#[derive(Accounts)]
+#[instruction(x:u8, y:u8, width:u8, height:u8)]
+pub struct SystemInitTiles<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        realloc = instance_index.to_account_info().data_len() + (ENTITY_ID_SIZE * width as usize * height as usize),
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
 
 #[derive(Accounts)]
 pub struct SystemInitFeature<'info> {
@@ -172,6 +213,15 @@ pub struct SystemInitFeature<'info> {
     )]
     pub instance_index: Box<Account<'info, InstanceIndex>>,    
     pub blueprint: Box<Account<'info, Blueprint>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_TREASURY,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, InstanceTreasury>>,
 
     //Registry
     #[account(
@@ -207,7 +257,8 @@ pub struct SystemInitPlayer<'info> {
     pub config: Box<Account<'info, Config>>,
     #[account(
         mut,
-        realloc = instance_index.to_account_info().data_len() + ENTITY_ID_SIZE,
+        // Room for the Player in both players and votes
+        realloc = instance_index.to_account_info().data_len() + PLAYER_INDEX_SIZE,
         realloc::payer = payer,
         realloc::zero = false,
         seeds=[
@@ -217,6 +268,19 @@ pub struct SystemInitPlayer<'info> {
         bump,
     )]
     pub instance_index: Box<Account<'info, InstanceIndex>>,    
+    #[account(
+        mut,
+        // Room for the Player's prize and claim, so prizes can be saved as soon as the game is Finished
+        realloc = escrow.to_account_info().data_len() + PAYOUT_SIZE,
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
     //Registry
     #[account(
@@ -262,6 +326,28 @@ pub struct CreateGameInstance<'info>{
         space= 8 + InstanceIndex::get_max_size() as usize + game_config.get_max_size() as usize
     )]
     pub instance_index: Box<Account<'info, InstanceIndex>>,    
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_TREASURY,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+        space= 8 + InstanceTreasury::get_max_size() as usize
+    )]
+    pub treasury: Box<Account<'info, InstanceTreasury>>,
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+        space= 8 + InstanceEscrow::get_max_size() as usize
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
     //Registry
     #[account(
@@ -301,6 +387,15 @@ pub struct ChangeGameState<'info> {
         bump,
     )]
     pub instance_index: Box<Account<'info, InstanceIndex>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
     // Registry
     #[account(
@@ -317,163 +412,575 @@ pub struct ChangeGameState<'info> {
     pub registry_instance: Account<'info, RegistryInstance>,
 
     // Entities Required
+    #[account(
+        constraint = player.instance == registry_instance.instance
+    )]
     pub player: Box<Account<'info, Entity>>,
 }
 
 #[derive(Accounts)]
-pub struct SpawnUnit<'info> {
-    #[account(mut)]
+pub struct ForceGameState<'info> {
     pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
 
-    //Action Bundle
     #[account(
-        seeds=[SEEDS_ABSIGNER],
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
         bump,
     )]
-    pub config: Box<Account<'info, Config>>,
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
     #[account(
         mut,
-        realloc = instance_index.to_account_info().data_len() + ENTITY_ID_SIZE,
-        realloc::payer = payer,
-        realloc::zero = false,
         seeds=[
-            SEEDS_INSTANCEINDEX,
+            SEEDS_ESCROW,
             registry_instance.key().as_ref()
         ],
         bump,
     )]
-    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
-    pub unit_blueprint: Account<'info, Blueprint>,
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+#[instruction(tile_ids: Vec<u64>)]
+pub struct SystemInitSpawnZone<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 
-    //Registry
     #[account(
-        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        mut,
+        realloc = instance_index.to_account_info().data_len() + 4 + (ENTITY_ID_SIZE * tile_ids.len()),
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
         bump,
-        seeds::program = registry_instance.registry.key()
     )]
-    pub registry_config: Account<'info, RegistryConfig>,
-    pub registry_program: Program<'info, Registry>,
-    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
 
-    //CoreDs
-    pub coreds: Program<'info, CoreDs>, 
+    // CoreDs
     pub registry_instance: Account<'info, RegistryInstance>,
-
-
-    #[account(mut)]
-    pub player: Box<Account<'info, Entity>>,
-    #[account(mut)]
-    pub tile: Box<Account<'info, Entity>>,
-    /// CHECK: Initalized through CPI
-    #[account(mut)]
-    pub unit: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct MoveUnit<'info> {
-    #[account(mut)]
+pub struct ClaimSpawnZone<'info> {
     pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
 
-    //Action Bundle
+    // Action Bundle
     #[account(
         seeds=[SEEDS_ABSIGNER],
         bump,
     )]
     pub config: Box<Account<'info, Config>>,
-    pub instance_index: Box<Account<'info, InstanceIndex>>,    
-
-    //Registry
     #[account(
-        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
         bump,
-        seeds::program = registry_instance.registry.key()
     )]
-    pub registry_config: Account<'info, RegistryConfig>,
-    pub registry_program: Program<'info, Registry>,
-    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
 
-    //CoreDs
-    pub coreds: Program<'info, CoreDs>, 
+    // CoreDs
     pub registry_instance: Account<'info, RegistryInstance>,
 
+    // Entities Required
     #[account(
-        mut,
-        constraint = from.instance == registry_instance.instance
+        constraint = player.instance == registry_instance.instance
     )]
-    pub from: Box<Account<'info, Entity>>,
+    pub player: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct CrankPhase<'info> {
+    pub payer: Signer<'info>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
     #[account(
         mut,
-        constraint = to.instance == registry_instance.instance
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
     )]
-    pub to: Box<Account<'info, Entity>>,
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
     #[account(
         mut,
-        constraint = unit.instance == registry_instance.instance
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
     )]
-    pub unit: Box<Account<'info, Entity>>,
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
 }
 
 #[derive(Accounts)]
-pub struct AttackTile <'info> {
+#[instruction(max_players: Option<u16>, starting_cards: Option<Vec<Pubkey>>)]
+pub struct UpdateGameConfig<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 
-    //Action Bundle
+    // Only grows, so swapping in fewer cards doesn't give back rent
     #[account(
-        seeds=[SEEDS_ABSIGNER],
+        mut,
+        realloc = instance_index.to_account_info().data_len() + (32 * starting_cards.as_ref().map_or(0, |cards| cards.len().saturating_sub(instance_index.config.starting_cards.len()))),
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
         bump,
     )]
-    pub config: Box<Account<'info, Config>>,
-    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
 
-    //Registry
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+pub struct KickPlayer<'info> {
+    pub payer: Signer<'info>,
+
+    // Action Bundle
     #[account(
-        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        seeds=[SEEDS_ABSIGNER],
         bump,
-        seeds::program = registry_instance.registry.key()
     )]
-    pub registry_config: Account<'info, RegistryConfig>,
-    pub registry_program: Program<'info, Registry>,
-    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
-
-    //CoreDs
-    pub coreds: Program<'info, CoreDs>, 
-    pub registry_instance: Account<'info, RegistryInstance>,
-
+    pub config: Box<Account<'info, Config>>,
     #[account(
         mut,
-        constraint = attacker.instance == registry_instance.instance
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
     )]
-    pub attacker: Box<Account<'info, Entity>>,
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
     #[account(
         mut,
-        constraint = defender.instance == registry_instance.instance
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
     )]
-    pub defender: Box<Account<'info, Entity>>,
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Entities Required
     #[account(
-        mut,
-        constraint = defending_tile.instance == registry_instance.instance
+        constraint = player.instance == registry_instance.instance
     )]
-    pub defending_tile: Box<Account<'info, Entity>>,    
+    pub player: Box<Account<'info, Entity>>,
+    /// CHECK: Checked against the Player's key, gets their entry fee back
+    #[account(mut)]
+    pub player_wallet: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UseFeature<'info> {
-    #[account(mut)]
+pub struct TransferAuthority<'info> {
     pub payer: Signer<'info>,
-    pub system_program: Program<'info, System>,
 
-    //Action Bundle
     #[account(
-        seeds=[SEEDS_ABSIGNER],
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+pub struct EndTurn<'info> {
+    pub payer: Signer<'info>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Entities Required
+    #[account(
+        constraint = player.instance == registry_instance.instance
+    )]
+    pub player: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct SpawnUnit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        realloc = instance_index.to_account_info().data_len() + ENTITY_ID_SIZE,
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    pub unit_blueprint: Account<'info, Blueprint>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_TREASURY,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, InstanceTreasury>>,
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+
+    #[account(
+        mut,
+        constraint = player.instance == registry_instance.instance
+    )]
+    pub player: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = tile.instance == registry_instance.instance
+    )]
+    pub tile: Box<Account<'info, Entity>>,
+    /// CHECK: Initalized through CPI
+    #[account(mut)]
+    pub unit: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MoveUnit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        mut,
+        constraint = from.instance == registry_instance.instance
+    )]
+    pub from: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = to.instance == registry_instance.instance
+    )]
+    pub to: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = unit.instance == registry_instance.instance
+    )]
+    pub unit: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct AttackTile <'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Randomness
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_COMMITMENT,
+            registry_instance.key().as_ref(),
+            payer.key().as_ref()
+        ],
+        bump,
+    )]
+    pub commitment: Box<Account<'info, RandomnessCommitment>>,
+    /// CHECK: Checked against the SlotHashes sysvar id
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        mut,
+        constraint = attacker.instance == registry_instance.instance
+    )]
+    pub attacker: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = attacking_player.instance == registry_instance.instance
+    )]
+    pub attacking_player: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = attacking_tile.instance == registry_instance.instance
+    )]
+    pub attacking_tile: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = defender.instance == registry_instance.instance
+    )]
+    pub defender: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = defending_tile.instance == registry_instance.instance
+    )]
+    pub defending_tile: Box<Account<'info, Entity>>,    
+}
+
+#[derive(Accounts)]
+pub struct UseFeature<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        mut,
+        constraint = unit.instance == registry_instance.instance
+    )]
+    pub unit: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = tile.instance == registry_instance.instance
+    )]
+    pub tile: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = feature.instance == registry_instance.instance
+    )]
+    pub feature: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct UpgradeFeature<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_TREASURY,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, InstanceTreasury>>,
+
+    #[account(
+        constraint = tile.instance == registry_instance.instance
+    )]
+    pub tile: Box<Account<'info, Entity>>,
+    #[account(
+        mut,
+        constraint = feature.instance == registry_instance.instance
+    )]
+    pub feature: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct BuyTile<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
         bump,
     )]
-    pub config: Box<Account<'info, Config>>,
     pub instance_index: Box<Account<'info, InstanceIndex>>,    
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_TREASURY,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub treasury: Box<Account<'info, InstanceTreasury>>,
 
     //Registry
     #[account(
@@ -489,21 +996,82 @@ pub struct UseFeature<'info> {
     pub coreds: Program<'info, CoreDs>, 
     pub registry_instance: Account<'info, RegistryInstance>,
 
+    /// CHECK: Checked against the Tile's current Owner if it's being resold, otherwise unused
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+
     #[account(
-        mut,
-        constraint = unit.instance == registry_instance.instance
+        constraint = player.instance == registry_instance.instance
     )]
-    pub unit: Box<Account<'info, Entity>>,
+    pub player: Box<Account<'info, Entity>>,
     #[account(
         mut,
         constraint = tile.instance == registry_instance.instance
     )]
     pub tile: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct ListTile<'info> {
+    pub payer: Signer<'info>,
+
+    //Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,    
+
+    //Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    //CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
     #[account(
         mut,
-        constraint = feature.instance == registry_instance.instance
+        constraint = tile.instance == registry_instance.instance
     )]
-    pub feature: Box<Account<'info, Entity>>,
+    pub tile: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct CommitRandomness<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init_if_needed,
+        payer=payer,
+        seeds=[
+            SEEDS_COMMITMENT,
+            registry_instance.key().as_ref(),
+            payer.key().as_ref()
+        ],
+        bump,
+        space= 8 + RandomnessCommitment::get_max_size() as usize
+    )]
+    pub commitment: Box<Account<'info, RandomnessCommitment>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
 }
 
 #[derive(Accounts)]
@@ -511,16 +1079,215 @@ pub struct ReclaimSol<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+    /// CHECK: Checked against whoever paid for each Entity being closed, gets the rent back
+    #[account(mut)]
+    pub benefactor: AccountInfo<'info>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
+
+    // Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    // CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveGame<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
 
     // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
     // Registry
+    #[account(
+        seeds = [SEEDS_REGISTRYSIGNER.as_slice()],
+        bump,
+        seeds::program = registry_instance.registry.key()
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    pub registry_program: Program<'info, Registry>,
+    pub ab_registration: Box<Account<'info, ActionBundleRegistration>>,
+
+    // CoreDs
+    pub coreds: Program<'info, CoreDs>, 
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Entities Required
+    #[account(
+        mut,
+        constraint = player.instance == registry_instance.instance
+    )]
+    pub player: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
+
+    // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Entities Required
+    #[account(
+        constraint = player.instance == registry_instance.instance
+    )]
+    pub player: Box<Account<'info, Entity>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckVictory<'info> {
+    pub payer: Signer<'info>,
+
+    // Action Bundle
+    #[account(
+        seeds=[SEEDS_ABSIGNER],
+        bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_INSTANCEINDEX,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub instance_index: Box<Account<'info, InstanceIndex>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ESCROW,
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, InstanceEscrow>>,
 
     // CoreDs
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Entities Required
+    #[account(
+        constraint = player.instance == registry_instance.instance
+    )]
+    pub player: Box<Account<'info, Entity>>,
 }
 
 
 /********************************************UTIL Fns */
+
+/**
+ * Accounts needed to ask the Registry to modify an Entity's components
+ * Lets helper fns share the CPI without needing the full instruction context
+ */
+pub struct RegistryCpi<'a, 'info> {
+    pub registry_program: AccountInfo<'info>,
+    pub registry_config: AccountInfo<'info>,
+    pub action_bundle: AccountInfo<'info>,
+    pub action_bundle_registration: AccountInfo<'info>,
+    pub core_ds: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+}
+
+impl<'a, 'info> RegistryCpi<'a, 'info> {
+    pub fn modify_component(&self, entity: AccountInfo<'info>, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+        let modify_ctx = CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            registry::cpi::accounts::ModifyComponent {
+                registry_config: self.registry_config.clone(),
+                entity,
+                action_bundle: self.action_bundle.clone(),
+                action_bundle_registration: self.action_bundle_registration.clone(),
+                core_ds: self.core_ds.clone(),
+            },
+            self.signer_seeds
+        );
+        registry::cpi::req_modify_component(modify_ctx, components)
+    }
+}
+
 pub fn compute_comp_arr_max_size(components: &Vec<SerializedComponent>) -> usize {
     let mut max_size:usize = 0;
     for comp in components {
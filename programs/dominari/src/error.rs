@@ -8,7 +8,7 @@ pub enum DominariError {
     #[msg("Instance already has max players!")]
     PlayerCountExceeded,
 
-    #[msg("Players can only PAUSE or PLAY their game!")]
+    #[msg("Game can't move to that phase from the current one!")]
     InvalidPlayPhase,
 
     #[msg("Only players in this game can call this function!")]
@@ -19,6 +19,75 @@ pub enum DominariError {
 
     #[msg("Invalid Accounts!")]
     InvalidAccounts,
+
+    #[msg("Payer can't reclaim that entity yet!")]
+    CannotReclaim,
+
+    #[msg("No randomness commitment to reveal!")]
+    NoCommitment,
+
+    #[msg("Reveal doesn't match the commitment!")]
+    InvalidReveal,
+
+    #[msg("Commitment can only be revealed in a later slot!")]
+    CommitmentPending,
+
+    #[msg("Commitment is too old to reveal, commit again!")]
+    CommitmentExpired,
+
+    #[msg("Commitment was made for a different actor or target!")]
+    CommitmentMismatch,
+
+    #[msg("Previous commitment hasn't been used or expired yet!")]
+    CommitmentOutstanding,
+
+    #[msg("Game doesn't have that victory condition!")]
+    InvalidVictoryCondition,
+
+    #[msg("Victory condition hasn't been met!")]
+    VictoryNotMet,
+
+    #[msg("Player already voted for that phase!")]
+    AlreadyVoted,
+
+    #[msg("Only the instance authority can call this function!")]
+    InvalidAuthority,
+
+    #[msg("It's not this player's turn!")]
+    NotYourTurn,
+
+    #[msg("Turn hasn't timed out yet!")]
+    TurnNotOver,
+
+    #[msg("Game isn't turn based!")]
+    NotTurnBased,
+
+    #[msg("Game config isn't valid!")]
+    InvalidGameConfig,
+
+    #[msg("Game doesn't have that team!")]
+    InvalidTeam,
+
+    #[msg("Phase hasn't run out yet!")]
+    PhaseNotOver,
+
+    #[msg("Map doesn't have that spawn zone!")]
+    InvalidSpawnZone,
+
+    #[msg("Spawn zone is already claimed!")]
+    SpawnZoneTaken,
+
+    #[msg("Prizes can only be claimed once the game is Finished!")]
+    PrizeNotReady,
+
+    #[msg("Player has nothing to claim!")]
+    NothingToClaim,
+
+    #[msg("Escrow doesn't hold enough for this payout!")]
+    EscrowShort,
+
+    #[msg("Instance Index can't shrink by more than it holds!")]
+    IndexUnderflow,
 }
 
 #[error_code]
@@ -58,4 +127,46 @@ pub enum ComponentErrors {
 
     #[msg("Invalid Location")]
     InvalidLocation,
+
+    #[msg("Feature is recovering from last use")]
+    FeatureRecovering,
+
+    #[msg("Feature has no uses left")]
+    FeatureExhausted,
+
+    #[msg("Destination is not a linked portal")]
+    InvalidPortal,
+
+    #[msg("Player hand is full")]
+    HandFull,
+
+    #[msg("Feature has nothing to drop")]
+    EmptyDropTable,
+
+    #[msg("Feature has no damage or range to attack with")]
+    FeatureUnarmed,
+
+    #[msg("Feature doesn't have ranks")]
+    FeatureNotUpgradeable,
+
+    #[msg("Feature is already max rank")]
+    FeatureMaxRank,
+
+    #[msg("Player already owns this tile")]
+    TileAlreadyOwned,
+
+    #[msg("Tile isn't for sale")]
+    TileNotForSale,
+
+    #[msg("Path steps must be between adjacent tiles")]
+    InvalidPath,
+
+    #[msg("Can't move through enemy units")]
+    EnemyInPath,
+
+    #[msg("Unit can't cross that terrain")]
+    ImpassableTerrain,
+
+    #[msg("Units can only spawn in the player's spawn zone or next to their territory")]
+    InvalidSpawnTile,
 }
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 use crate::account::PlayPhase;
+use crate::state::VictoryCondition;
 
 #[event]
 pub struct NewWorldInstance {
@@ -42,9 +43,12 @@ pub struct TroopMovement {
 pub struct TileAttacked {
     pub instance:u64,
     pub attacker: u64,
+    pub attacking_tile: u64,
     pub defender: u64,
     pub defending_tile: u64,
-    pub damage: u64
+    pub damage: u64,
+    // Damage the defender dealt back, None if it didn't strike back
+    pub counter_damage: Option<u64>
 }
 
 #[event]
@@ -52,4 +56,153 @@ pub struct GameStateChanged {
     pub instance: u64,
     pub player: u64,
     pub new_state: PlayPhase
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct GameStateVote {
+    pub instance: u64,
+    pub player: u64,
+    pub proposed_state: PlayPhase,
+    pub votes: u16,
+    pub votes_needed: u16
+}
+
+#[event]
+pub struct GameStateForced {
+    pub instance: u64,
+    pub authority: Pubkey,
+    pub new_state: PlayPhase
+}
+
+#[event]
+pub struct FeatureLooted {
+    pub instance: u64,
+    pub feature: u64,
+    pub player: u64,
+    pub blueprint: Pubkey
+}
+
+#[event]
+pub struct FeatureUpgraded {
+    pub instance: u64,
+    pub feature: u64,
+    pub rank: u8,
+    pub cost: u64
+}
+
+#[event]
+pub struct CostPaid {
+    pub instance: u64,
+    pub payer: Pubkey,
+    pub entity: u64,
+    pub lamports: u64
+}
+
+#[event]
+pub struct TileBought {
+    pub instance: u64,
+    pub tile: u64,
+    pub player: u64,
+    pub price: u64
+}
+
+#[event]
+pub struct TileListed {
+    pub instance: u64,
+    pub tile: u64,
+    pub price: u64
+}
+
+#[event]
+pub struct EntityReclaimed {
+    pub instance: u64,
+    pub entity: u64,
+    pub benefactor: Pubkey
+}
+
+#[event]
+pub struct RandomnessCommitted {
+    pub instance: u64,
+    pub player: Pubkey,
+    pub slot: u64,
+    pub actor: u64,
+    pub target: u64
+}
+
+#[event]
+pub struct UnitKilled {
+    pub instance: u64,
+    pub attacker: u64,
+    pub defender: u64,
+    pub player: u64,
+    pub score: u64
+}
+
+#[event]
+pub struct GameWon {
+    pub instance: u64,
+    pub player: u64,
+    pub team: Option<u8>,
+    pub condition: VictoryCondition
+}
+
+#[event]
+pub struct TurnEnded {
+    pub instance: u64,
+    pub turn: u64,
+    pub player: u64,
+    pub next_player: u64,
+    pub skipped: bool
+}
+
+#[event]
+pub struct GameConfigUpdated {
+    pub instance: u64,
+    pub max_players: u16,
+    pub starting_cards: Vec<Pubkey>
+}
+
+#[event]
+pub struct PlayerKicked {
+    pub instance: u64,
+    pub player: u64
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub instance: u64,
+    pub old_authority: Pubkey,
+    pub new_authority: Option<Pubkey>
+}
+
+#[event]
+pub struct PlayerLeft {
+    pub instance: u64,
+    pub player: u64,
+    pub forfeited: bool
+}
+
+#[event]
+pub struct PhaseExpired {
+    pub instance: u64,
+    pub cranker: Pubkey,
+    pub old_state: PlayPhase,
+    pub new_state: PlayPhase,
+    pub winner: Option<u64>,
+    pub team: Option<u8>
+}
+
+#[event]
+pub struct SpawnZoneClaimed {
+    pub instance: u64,
+    pub player: u64,
+    pub zone: u8
+}
+
+#[event]
+pub struct PrizeClaimed {
+    pub instance: u64,
+    pub player: u64,
+    pub amount: u64,
+    pub refund: bool
+}
@@ -1,5 +1,4 @@
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::*;
 use std::collections::BTreeMap;
 
 pub mod account;
@@ -9,6 +8,7 @@ pub mod error;
 pub mod event;
 pub mod component;
 pub mod state;
+pub mod randomness;
 
 use account::*;
 use context::*;
@@ -17,8 +17,9 @@ use error::*;
 use event::*;
 use component::*;
 use state::*;
+use randomness::*;
 
-use core_ds::account::MaxSize;
+use core_ds::account::{MaxSize, Entity};
 use core_ds::state::SerializedComponent;
 
 declare_id!("3YdayPtujByJ1g1DWEUh7vpg78gZL49FWyD5rDGyof9T");
@@ -40,7 +41,8 @@ pub mod dominari {
         Ok(())
     }
 
-    pub fn system_init_map(ctx:Context<SystemInitMap>, entity_id:u64, max_x: u8, max_y: u8) -> Result<()> {
+    pub fn system_init_map(ctx:Context<SystemInitMap>, max_x: u8, max_y: u8) -> Result<()> {
+        let entity_id = ctx.accounts.instance_index.allocate_entity_ids(1);
         let reference = &ctx.accounts.config.components;
         let config_seeds:&[&[u8]] = &[
             SEEDS_ABSIGNER,
@@ -90,64 +92,20 @@ pub mod dominari {
         Ok(())
     }
 
-    pub fn system_init_tile(ctx:Context<SystemInitTile>, entity_id:u64, x:u8, y:u8, cost:u64) -> Result<()> {
+    /**
+     * Owner can pre-assign the Tile to someone other than the Admin
+     * Pre-owned Tiles can only be bought once their owner lists them
+     */
+    pub fn system_init_tile(ctx:Context<SystemInitTile>, x:u8, y:u8, cost:u64, terrain: Terrain, owner: Option<Pubkey>) -> Result<()> {
         // Tile can only be instanced by Admin
         // So we can trust in the input
+        if ctx.accounts.instance_index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        let entity_id = ctx.accounts.instance_index.allocate_entity_ids(1);
         let reference = &ctx.accounts.config.components;
-
-        // Tile has Metadata, Location, Feature, Occupant, Owner and Cost components
-        let mut components: BTreeMap<Pubkey, SerializedComponent> = BTreeMap::new();
-        let metadata = ComponentMetadata {
-            name: format!("Tile ({x}, {y})"),
-            entity_type: EntityType::Tile,
-            registry_instance: ctx.accounts.registry_instance.key(),
-        }.try_to_vec().unwrap();
-        components.insert(reference.metadata.key(), SerializedComponent { 
-            max_size: ComponentMetadata::get_max_size(),
-            data: metadata
-        });
-
-        let location = ComponentLocation {
-            x,
-            y,
-        }.try_to_vec().unwrap();
-        components.insert(reference.location.key(), SerializedComponent { 
-            max_size: ComponentLocation::get_max_size(),
-            data: location
-        });
-
-        let feature = ComponentFeature {
-            feature_id: None,
-        }.try_to_vec().unwrap();
-        components.insert(reference.feature.key(), SerializedComponent { 
-            max_size: ComponentFeature::get_max_size(),
-            data: feature
-        });
-
-        let occupant = ComponentOccupant {
-            occupant_id: None,
-        }.try_to_vec().unwrap();
-        components.insert(reference.occupant.key(), SerializedComponent { 
-            max_size: ComponentOccupant::get_max_size(),
-            data: occupant
-        });
-
-        let owner = ComponentOwner {
-            owner: Some(ctx.accounts.payer.key()),
-            player: None,
-        }.try_to_vec().unwrap();
-        components.insert(reference.owner.key(), SerializedComponent { 
-            max_size: ComponentOwner::get_max_size(),
-            data: owner
-        });
-
-        let cost_component = ComponentCost {
-            lamports: cost,
-        }.try_to_vec().unwrap();
-        components.insert(reference.cost.key(), SerializedComponent { 
-            max_size: ComponentCost::get_max_size(),
-            data: cost_component
-        });
+        let owner = owner.unwrap_or(ctx.accounts.instance_index.creator);
+        let components = get_tile_components(reference, ctx.accounts.registry_instance.key(), owner, x, y, cost, terrain);
 
         let config_seeds:&[&[u8]] = &[
             SEEDS_ABSIGNER,
@@ -175,17 +133,116 @@ pub mod dominari {
         Ok(())
     }
     
-    pub fn system_init_feature(ctx:Context<SystemInitFeature>, entity_id: u64) -> Result<()> {
+    /**
+     * Inits a width x height rectangle of Tiles starting at (x, y) in one instruction
+     * Tiles go row by row, and get the next entity ids, entity accounts (remaining accounts) and terrain in that order
+     * Terrain can be left empty to make every Tile Plains
+     */
+    pub fn system_init_tiles<'info>(ctx:Context<'_, '_, '_, 'info, SystemInitTiles<'info>>, x:u8, y:u8, width:u8, height:u8, cost:u64, terrain: Vec<Terrain>) -> Result<()> {
+        if ctx.accounts.instance_index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        let reference = &ctx.accounts.config.components;
+
+        let count = width as usize * height as usize;
+        if count == 0 || ctx.remaining_accounts.len() != count || (terrain.len() != 0 && terrain.len() != count) {
+            return err!(DominariError::InvalidAccounts)
+        }
+        if x.checked_add(width - 1).is_none() || y.checked_add(height - 1).is_none() {
+            return err!(ComponentErrors::InvalidLocation)
+        }
+        let first_id = ctx.accounts.instance_index.allocate_entity_ids(count as u64);
+        let entity_ids:Vec<u64> = (first_id..first_id + count as u64).collect();
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+
+        for (i, tile_entity) in ctx.remaining_accounts.iter().enumerate() {
+            let tile_x = x + (i % width as usize) as u8;
+            let tile_y = y + (i / width as usize) as u8;
+            let tile_terrain = terrain.get(i).cloned().unwrap_or(Terrain::Plains);
+            let components = get_tile_components(reference, ctx.accounts.registry_instance.key(), ctx.accounts.instance_index.creator, tile_x, tile_y, cost, tile_terrain);
+
+            let init_entity_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::InitEntity{
+                    entity: tile_entity.clone(),
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    registry_instance: ctx.accounts.registry_instance.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::init_entity(init_entity_ctx, entity_ids[i], components)?;
+        }
+
+        ctx.accounts.instance_index.tiles.extend(entity_ids);
+        Ok(())
+    }
+
+    /**
+     * Adds a spawn zone made up of the given Tiles, zones are numbered in the order they're made
+     * Map setup only, so the authority can only add zones in the Lobby
+     */
+    pub fn system_init_spawn_zone(ctx:Context<SystemInitSpawnZone>, tile_ids: Vec<u64>) -> Result<()> {
+        let index = &mut ctx.accounts.instance_index;
+        if index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        if index.play_phase != PlayPhase::Lobby {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+        // Zones can't overlap, and zone numbers have to fit in a u8
+        if tile_ids.len() == 0 || index.spawn_zones.len() > u8::MAX as usize {
+            return err!(DominariError::InvalidSpawnZone)
+        }
+        for (i, tile_id) in tile_ids.iter().enumerate() {
+            if !index.tiles.contains(tile_id) || tile_ids[..i].contains(tile_id) || index.spawn_zones.iter().any(|zone| zone.contains(tile_id)) {
+                return err!(DominariError::InvalidSpawnZone)
+            }
+        }
+        index.spawn_zones.push(tile_ids);
+        Ok(())
+    }
+
+    /**
+     * Features can be placed by the authority or creator while setting up the map in the Lobby, and by tile owners during Build
+     */
+    pub fn system_init_feature(ctx:Context<SystemInitFeature>) -> Result<()> {
+        let play_phase = &ctx.accounts.instance_index.play_phase;
+        let is_authority = ctx.accounts.instance_index.authority.key() == ctx.accounts.payer.key();
+        let is_creator = ctx.accounts.instance_index.creator.key() == ctx.accounts.payer.key();
+        match play_phase {
+            PlayPhase::Build => {},
+            PlayPhase::Lobby if is_authority || is_creator => {},
+            _ => return err!(DominariError::InvalidPlayPhase)
+        }
+        let entity_id = ctx.accounts.instance_index.allocate_entity_ids(1);
         // Check to make sure tile can be modified by payer
+        // The authority looks after Tiles nobody has bought yet
         let reference = &ctx.accounts.config.components;
         let tile_owner_component = ctx.accounts.tile_entity.components.get(&reference.owner).unwrap();
         let tile_owner:ComponentOwner = ComponentOwner::try_from_slice(&tile_owner_component.data.as_slice()).unwrap();
         
-        if tile_owner.owner.unwrap().key() != ctx.accounts.payer.key() {
+        if tile_owner.owner != Some(ctx.accounts.payer.key()) && !(is_authority && ctx.accounts.instance_index.is_unclaimed(&tile_owner)) {
             return err!(ComponentErrors::InvalidOwner)
         }
 
-        // TODO: Check Blueprint 'cost' component and transfer that fee to build the Feature
+        // Pay the Blueprint's cost to build the Feature into the Treasury
+        let cost = pay_blueprint_cost(
+            &ctx.accounts.blueprint,
+            reference,
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.system_program.to_account_info()
+        )?;
 
         // Create Feature entity
         let mut components: BTreeMap<Pubkey, SerializedComponent> = BTreeMap::new();
@@ -205,7 +262,8 @@ pub mod dominari {
         
         let owner = ComponentOwner {
             owner: tile_owner.owner,
-            player: None,
+            player: tile_owner.player,
+            team: tile_owner.team,
         }.try_to_vec().unwrap();
         components.insert(reference.owner.key(), SerializedComponent { 
             max_size: ComponentOwner::get_max_size(),
@@ -250,6 +308,15 @@ pub mod dominari {
         registry::cpi::init_entity(init_entity_ctx, entity_id, components)?;
         ctx.accounts.instance_index.features.push(entity_id);
 
+        if cost > 0 {
+            emit!(CostPaid {
+                instance: ctx.accounts.registry_instance.instance,
+                payer: ctx.accounts.payer.key(),
+                entity: entity_id,
+                lamports: cost
+            });
+        }
+
         // Modify the Tile Entity with the new Feature
         let tile_feature_component = ctx.accounts.tile_entity.components.get(&reference.feature).unwrap();
         let mut tile_feature:ComponentFeature = ComponentFeature::try_from_slice(&tile_feature_component.data.as_slice()).unwrap();
@@ -299,10 +366,19 @@ pub mod dominari {
         // Set up Instance Index
         ctx.accounts.instance_index.config = game_config; 
         ctx.accounts.instance_index.authority = ctx.accounts.payer.key();
+        ctx.accounts.instance_index.creator = ctx.accounts.payer.key();
+        ctx.accounts.instance_index.next_entity_id = FIRST_ENTITY_ID;
+        // Starts the Lobby clock
+        ctx.accounts.instance_index.set_play_phase(PlayPhase::Lobby, Clock::get().unwrap().slot);
+        ctx.accounts.treasury.instance = instance;
+        ctx.accounts.escrow.instance = instance;
         Ok(())
     }
 
-    pub fn system_init_player(ctx:Context<SystemInitPlayer>, entity_id: u64, name:String, image: String ) -> Result <()> {
+    /**
+     * In team games the player can pick a team, or be put on the smallest one
+     */
+    pub fn system_init_player(ctx:Context<SystemInitPlayer>, name:String, image: String, team: Option<u8>) -> Result <()> {
         let reference = &ctx.accounts.config.components;
         // Optional: Fail if too many players already in the instance
         if ctx.accounts.instance_index.config.max_players == ctx.accounts.instance_index.players.len() as u16 {
@@ -312,6 +388,8 @@ pub mod dominari {
         if name.len() > STRING_MAX_SIZE as usize || image.len() > STRING_MAX_SIZE as usize {
             return err!(ComponentErrors::StringTooLong)
         }
+        let team = ctx.accounts.instance_index.assign_team(team)?;
+        let entity_id = ctx.accounts.instance_index.allocate_entity_ids(1);
 
         // Create Player Entity
         // Player has: Metadata and Player Stats
@@ -366,41 +444,370 @@ pub mod dominari {
         
         // Add player entity to instance index
         ctx.accounts.instance_index.players.push(entity_id);
+        if let Some(team) = team {
+            ctx.accounts.instance_index.player_teams.insert(entity_id, team);
+        }
+        ctx.accounts.instance_index.player_units.insert(entity_id, 0);
+        ctx.accounts.instance_index.player_scores.insert(entity_id, 0);
+
+        // Entry fee goes into the prize pool
+        let entry_fee = ctx.accounts.instance_index.config.entry_fee;
+        if entry_fee > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                }
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, entry_fee)?;
+            ctx.accounts.escrow.pot += entry_fee;
+        }
 
         Ok(())
     }
 
     /**
      * Can only be called by a player that's in the game
+     * Each call is a vote for the phase, once enough players have voted the game moves to it
+     * Voting for a different phase than the one proposed starts a new vote
      */
     pub fn change_game_state(ctx:Context<ChangeGameState>, game_state: PlayPhase) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
         if !ctx.accounts.instance_index.players.contains(&ctx.accounts.player.entity_id) {
             return err!(DominariError::InvalidPlayer)
         }
 
-        ctx.accounts.instance_index.play_phase = game_state.clone();
+        let player_stats_c = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidPlayer)
+        }
+
+        let index = &mut ctx.accounts.instance_index;
+        let votes_needed = match index.play_phase.votes_needed(&game_state, index.players.len()) {
+            Some(votes_needed) => votes_needed,
+            None => return err!(DominariError::InvalidPlayPhase)
+        };
 
-        emit!(GameStateChanged {
+        if index.proposed_phase != Some(game_state.clone()) {
+            index.proposed_phase = Some(game_state.clone());
+            index.votes = vec![];
+        }
+        if index.votes.contains(&ctx.accounts.player.entity_id) {
+            return err!(DominariError::AlreadyVoted)
+        }
+        index.votes.push(ctx.accounts.player.entity_id);
+
+        emit!(GameStateVote {
             instance: ctx.accounts.registry_instance.instance,
             player: ctx.accounts.player.entity_id,
+            proposed_state: game_state.clone(),
+            votes: index.votes.len() as u16,
+            votes_needed: votes_needed as u16
+        });
+
+        if index.votes.len() >= votes_needed {
+            index.set_play_phase(game_state.clone(), Clock::get().unwrap().slot);
+
+            emit!(GameStateChanged {
+                instance: ctx.accounts.registry_instance.instance,
+                player: ctx.accounts.player.entity_id,
+                new_state: game_state
+            });
+        }
+        settle_escrow(&mut ctx.accounts.escrow, &ctx.accounts.instance_index);
+        Ok(())
+    }
+
+    /**
+     * Lets the instance authority make any allowed phase transition without a vote
+     */
+    pub fn force_game_state(ctx:Context<ForceGameState>, game_state: PlayPhase) -> Result<()> {
+        if ctx.accounts.instance_index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+
+        let index = &mut ctx.accounts.instance_index;
+        if index.play_phase.votes_needed(&game_state, index.players.len()).is_none() {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+        index.set_play_phase(game_state.clone(), Clock::get().unwrap().slot);
+
+        emit!(GameStateForced {
+            instance: ctx.accounts.registry_instance.instance,
+            authority: ctx.accounts.payer.key(),
             new_state: game_state
         });
+        settle_escrow(&mut ctx.accounts.escrow, &ctx.accounts.instance_index);
+        Ok(())
+    }
+
+    /**
+     * Anyone can move the game on once the current phase runs out
+     * Play running out ends the game, whoever met a Victory Condition wins before the side with the highest score in the Instance Index
+     * The Lobby running out with too few players abandons the game
+     * Remaining Accounts: [Player, Tiles they own...] to claim TileControl for that player, other conditions go by the Instance Index
+     */
+    pub fn crank_phase<'info>(ctx:Context<'_, '_, '_, 'info, CrankPhase<'info>>) -> Result<()> {
+        let slot = Clock::get().unwrap().slot;
+        match ctx.accounts.instance_index.phase_deadline {
+            Some(deadline) if slot > deadline => {},
+            _ => return err!(DominariError::PhaseNotOver)
+        }
+
+        let old_state = ctx.accounts.instance_index.play_phase.clone();
+        let new_state = match ctx.accounts.instance_index.get_expired_phase() {
+            Some(new_state) => new_state,
+            None => return err!(DominariError::InvalidPlayPhase)
+        };
+
+        if old_state == PlayPhase::Play {
+            let index = &ctx.accounts.instance_index;
+            let claimant: Option<Account<Entity>> = match ctx.remaining_accounts.get(0) {
+                Some(player_info) => {
+                    let player:Account<Entity> = Account::try_from(player_info)?;
+                    if player.instance != ctx.accounts.registry_instance.instance || !index.players.contains(&player.entity_id) {
+                        return err!(DominariError::InvalidAccounts)
+                    }
+                    Some(player)
+                },
+                None => None
+            };
+
+            // Conditions are checked in the order the config lists them
+            let mut winner = None;
+            for condition in index.config.victory_conditions.iter() {
+                winner = match (condition, &claimant) {
+                    (VictoryCondition::TileControl { tiles: _, deadline: _ }, Some(player)) => {
+                        match victory_condition_met(condition, player, &ctx.remaining_accounts[1..], index, &ctx.accounts.config.components, slot)? {
+                            true => Some(player.entity_id),
+                            false => None
+                        }
+                    },
+                    _ => get_index_victor(condition, index)
+                };
+                if winner.is_some() {
+                    break;
+                }
+            }
+            let winner = winner.or_else(|| get_score_leader(index));
+            ctx.accounts.instance_index.winner = winner;
+        }
+        ctx.accounts.instance_index.set_play_phase(new_state.clone(), slot);
+
+        let winner = ctx.accounts.instance_index.winner;
+        emit!(PhaseExpired {
+            instance: ctx.accounts.registry_instance.instance,
+            cranker: ctx.accounts.payer.key(),
+            old_state,
+            new_state,
+            winner,
+            team: winner.and_then(|winner| ctx.accounts.instance_index.get_team(winner))
+        });
+        settle_escrow(&mut ctx.accounts.escrow, &ctx.accounts.instance_index);
         Ok(())
     }
 
-    pub fn spawn_unit(ctx:Context<SpawnUnit>, unit_id: u64) -> Result<()> {
+    /**
+     * Players claim a spawn zone during Build, each zone only fits one player
+     * Claiming again moves the player to the new zone
+     */
+    pub fn claim_spawn_zone(ctx:Context<ClaimSpawnZone>, zone: u8) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        if ctx.accounts.instance_index.play_phase != PlayPhase::Build {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+        if !ctx.accounts.instance_index.players.contains(&ctx.accounts.player.entity_id) {
+            return err!(DominariError::InvalidPlayer)
+        }
+
+        let player_stats_c = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidPlayer)
+        }
+
+        let index = &mut ctx.accounts.instance_index;
+        if zone as usize >= index.spawn_zones.len() {
+            return err!(DominariError::InvalidSpawnZone)
+        }
+        if index.spawn_claims.iter().any(|(&player, &claimed)| claimed == zone && player != ctx.accounts.player.entity_id) {
+            return err!(DominariError::SpawnZoneTaken)
+        }
+        index.spawn_claims.insert(ctx.accounts.player.entity_id, zone);
+
+        emit!(SpawnZoneClaimed {
+            instance: ctx.accounts.registry_instance.instance,
+            player: ctx.accounts.player.entity_id,
+            zone
+        });
+        Ok(())
+    }
+
+    /**
+     * Authority can change the GameConfig while the game is still in the Lobby
+     * Starting cards are handed out on join, so they can only change before anyone joins
+     */
+    pub fn update_game_config(ctx:Context<UpdateGameConfig>, max_players: Option<u16>, starting_cards: Option<Vec<Pubkey>>) -> Result<()> {
+        let index = &mut ctx.accounts.instance_index;
+        if index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        if index.play_phase != PlayPhase::Lobby {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+
+        if let Some(max_players) = max_players {
+            if (max_players as usize) < index.players.len() || max_players == 0 {
+                return err!(DominariError::InvalidGameConfig)
+            }
+            index.config.max_players = max_players;
+        }
+        if let Some(starting_cards) = starting_cards {
+            if index.players.len() > 0 || starting_cards.len() as u64 > PLAYER_MAX_CARDS {
+                return err!(DominariError::InvalidGameConfig)
+            }
+            index.config.starting_cards = starting_cards;
+        }
+
+        emit!(GameConfigUpdated {
+            instance: ctx.accounts.registry_instance.instance,
+            max_players: index.config.max_players,
+            starting_cards: index.config.starting_cards.clone()
+        });
+        Ok(())
+    }
+
+    /**
+     * Authority can remove a player from the game while it's in the Lobby
+     * The Player entity is left alone so the player can reclaim it once the game is over
+     * Their entry fee goes back to their wallet
+     * A pending phase vote can pass once they're gone, same as when a player leaves
+     */
+    pub fn kick_player(ctx:Context<KickPlayer>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        let index = &mut ctx.accounts.instance_index;
+        if index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        if index.play_phase != PlayPhase::Lobby {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+        if !index.players.contains(&ctx.accounts.player.entity_id) {
+            return err!(DominariError::InvalidPlayer)
+        }
+        let player_stats_c = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.player_wallet.key() {
+            return err!(DominariError::InvalidAccounts)
+        }
+        let slot = Clock::get().unwrap().slot;
+        index.remove_player(ctx.accounts.player.entity_id, slot);
+        if let Some(new_state) = index.settle_vote(slot) {
+            emit!(GameStateChanged {
+                instance: ctx.accounts.registry_instance.instance,
+                player: ctx.accounts.player.entity_id,
+                new_state
+            });
+        }
+
+        let entry_fee = index.config.entry_fee;
+        if entry_fee > 0 {
+            pay_from_escrow(&ctx.accounts.escrow, &ctx.accounts.player_wallet.to_account_info(), entry_fee)?;
+            ctx.accounts.escrow.pot -= entry_fee;
+        }
+
+        emit!(PlayerKicked {
+            instance: ctx.accounts.registry_instance.instance,
+            player: ctx.accounts.player.entity_id,
+        });
+        Ok(())
+    }
+
+    /**
+     * Hands the instance over to a new authority, or renounces it for good if new_authority is None
+     * Unclaimed Tiles stay with the instance creator either way
+     */
+    pub fn transfer_authority(ctx:Context<TransferAuthority>, new_authority: Option<Pubkey>) -> Result<()> {
+        let index = &mut ctx.accounts.instance_index;
+        if index.authority.key() != ctx.accounts.payer.key() {
+            return err!(DominariError::InvalidAuthority)
+        }
+        // Nobody can sign for the default key, so nobody can be the authority after renouncing
+        index.authority = new_authority.unwrap_or_default();
+
+        emit!(AuthorityTransferred {
+            instance: ctx.accounts.registry_instance.instance,
+            old_authority: ctx.accounts.payer.key(),
+            new_authority
+        });
+        Ok(())
+    }
+
+    /**
+     * Turn based games only
+     * The current player can end their turn whenever, anyone can skip them once the turn times out
+     */
+    pub fn end_turn(ctx:Context<EndTurn>) -> Result<()> {
         let reference = &ctx.accounts.config.components;
-        // Check if the game is paused
         if ctx.accounts.instance_index.play_phase != PlayPhase::Play {
             return err!(DominariError::GamePaused)
         }
 
+        let turn_timeout = match ctx.accounts.instance_index.config.turn_timeout {
+            Some(turn_timeout) => turn_timeout,
+            None => return err!(DominariError::NotTurnBased)
+        };
+
+        if ctx.accounts.player.entity_id != ctx.accounts.instance_index.current_player {
+            return err!(DominariError::NotYourTurn)
+        }
+
+        let player_stats_c = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+        let slot = Clock::get().unwrap().slot;
+        let timed_out = slot > ctx.accounts.instance_index.turn_start + turn_timeout;
+        if player_stats.key.key() != ctx.accounts.payer.key() && !timed_out {
+            return err!(DominariError::TurnNotOver)
+        }
+
+        let turn = ctx.accounts.instance_index.turn;
+        ctx.accounts.instance_index.next_turn(slot);
+
+        emit!(TurnEnded {
+            instance: ctx.accounts.registry_instance.instance,
+            turn,
+            player: ctx.accounts.player.entity_id,
+            next_player: ctx.accounts.instance_index.current_player,
+            skipped: player_stats.key.key() != ctx.accounts.payer.key()
+        });
+        Ok(())
+    }
+
+    /**
+     * Starting units are placed during Build, in the player's spawn zone (or their own territory on maps without zones)
+     * During Play units spawn on the player's side's territory, or next to it
+     * For a Tile next to territory, pass the owned Tile or Feature it's next to as the only remaining account
+     */
+    pub fn spawn_unit<'info>(ctx:Context<'_, '_, '_, 'info, SpawnUnit<'info>>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        // Check if the game is paused
+        let play_phase = ctx.accounts.instance_index.play_phase.clone();
+        if play_phase != PlayPhase::Play && play_phase != PlayPhase::Build {
+            return err!(DominariError::GamePaused)
+        }
+
         // Check player belongs to payer
         let player_stats_component = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
         let mut player_stats = ComponentPlayerStats::try_from_slice(&player_stats_component.data.as_slice()).unwrap();
         if player_stats.key.key() != ctx.accounts.payer.key() {
             return err!(ComponentErrors::InvalidOwner)
         }
+        // Turns only start once the game is in Play
+        if play_phase == PlayPhase::Play {
+            ctx.accounts.instance_index.check_turn(Some(ctx.accounts.player.entity_id))?;
+        }
 
         // Check that the Tile is Empty
         let tile_occupant_component = ctx.accounts.tile.components.get(&reference.occupant).unwrap();
@@ -409,6 +816,25 @@ pub mod dominari {
             return err!(ComponentErrors::TileOccupied)
         }
 
+        // Check the Tile is somewhere the player can spawn
+        let side = ComponentOwner {
+            owner: None,
+            player: Some(ctx.accounts.player.entity_id),
+            team: ctx.accounts.instance_index.get_team(ctx.accounts.player.entity_id),
+        };
+        let tile_owner_c = ctx.accounts.tile.components.get(&reference.owner).unwrap();
+        let is_territory = ComponentOwner::try_from_slice(&tile_owner_c.data.as_slice()).unwrap().is_friendly(&side);
+        let can_spawn = match play_phase {
+            PlayPhase::Build if ctx.accounts.instance_index.spawn_zones.len() > 0 => {
+                ctx.accounts.instance_index.in_spawn_zone(ctx.accounts.player.entity_id, ctx.accounts.tile.entity_id)
+            },
+            PlayPhase::Build => is_territory,
+            _ => is_territory || is_next_to_territory(&ctx.accounts.tile, ctx.remaining_accounts.get(0), &ctx.accounts.instance_index, reference, &side)?
+        };
+        if !can_spawn {
+            return err!(ComponentErrors::InvalidSpawnTile)
+        }
+
         // Check the Blueprint is in Player Hand
         let card_idx = player_stats.cards.iter().position(|&card| card.key() == ctx.accounts.unit_blueprint.key());
 
@@ -419,7 +845,17 @@ pub mod dominari {
         // Modify Player Hand to remove Blueprint
         player_stats.cards.swap_remove(card_idx.unwrap());
 
+        // Pay the Blueprint's cost to spawn the Unit into the Treasury
+        let cost = pay_blueprint_cost(
+            &ctx.accounts.unit_blueprint,
+            reference,
+            ctx.accounts.payer.to_account_info(),
+            ctx.accounts.treasury.to_account_info(),
+            ctx.accounts.system_program.to_account_info()
+        )?;
+
         // Create Unit Entity
+        let unit_id = ctx.accounts.instance_index.allocate_entity_ids(1);
         let mut components: BTreeMap<Pubkey, SerializedComponent> = BTreeMap::new();
         // Add Metadata, Owner, Location, Active + Blueprint components
         let metadata_component = ComponentMetadata {
@@ -434,7 +870,8 @@ pub mod dominari {
         });
         let owner_component = ComponentOwner {  
             owner: Some(ctx.accounts.payer.key()),
-            player: Some(ctx.accounts.player.entity_id)
+            player: Some(ctx.accounts.player.entity_id),
+            team: ctx.accounts.instance_index.get_team(ctx.accounts.player.entity_id)
         }.try_to_vec().unwrap();
         components.insert(reference.owner.key(), SerializedComponent {
             max_size: ComponentOwner::get_max_size(),
@@ -480,6 +917,10 @@ pub mod dominari {
         registry::cpi::init_entity(init_entity_ctx, unit_id, components)?;
         // Add the new Unit Entity to Instance index
         ctx.accounts.instance_index.units.push(unit_id);
+        ctx.accounts.instance_index.add_living_unit(ctx.accounts.player.entity_id);
+        if !ctx.accounts.instance_index.deployed.contains(&ctx.accounts.player.entity_id) {
+            ctx.accounts.instance_index.deployed.push(ctx.accounts.player.entity_id);
+        }
 
         // Modify Tile to point to Unit Entity
         tile_occupant.occupant_id = Some(unit_id);
@@ -519,10 +960,24 @@ pub mod dominari {
             unit: unit_id
         });
 
+        if cost > 0 {
+            emit!(CostPaid {
+                instance: ctx.accounts.registry_instance.instance,
+                payer: ctx.accounts.payer.key(),
+                entity: unit_id,
+                lamports: cost
+            });
+        }
+
         Ok(())
     }
 
-    pub fn move_unit(ctx:Context<MoveUnit>) -> Result<()> {
+    /**
+     * Tiles between From and To are passed in order through remaining accounts
+     * An occupied tile on the path must be followed by its (friendly) occupant so the owner can be checked
+     * The path can't leave the map or step on a tile twice, From and To included
+     */
+    pub fn move_unit<'info>(ctx:Context<'_, '_, '_, 'info, MoveUnit<'info>>) -> Result<()> {
         let reference = &ctx.accounts.config.components;
         // Check if the game is paused
         if ctx.accounts.instance_index.play_phase != PlayPhase::Play {
@@ -556,6 +1011,7 @@ pub mod dominari {
         if unit_owner.owner.unwrap() != ctx.accounts.payer.key() {
             return err!(ComponentErrors::InvalidOwner)
         }
+        ctx.accounts.instance_index.check_turn(unit_owner.player)?;
         
         // Unit must be recovered from last used
         let clock = Clock::get().unwrap();
@@ -565,17 +1021,55 @@ pub mod dominari {
             return err!(ComponentErrors::UnitRecovering)
         }
 
-        // Distance between From and To must be < Unit's Movement
+        // Path from From to To must fit in the Unit's Movement
         let from_location_c = ctx.accounts.from.components.get(&reference.location).unwrap();
         let from_location = ComponentLocation::try_from_slice(&from_location_c.data.as_slice()).unwrap();
 
         let to_location_c = ctx.accounts.to.components.get(&reference.location).unwrap();
         let to_location = ComponentLocation::try_from_slice(&to_location_c.data.as_slice()).unwrap();
-        
-        let distance:f64 = (((to_location.x as f64 - from_location.x as f64).powf(2_f64) + (to_location.y as f64 - from_location.y as f64).powf(2_f64)) as f64).sqrt();
+
+        let metric = &ctx.accounts.instance_index.config.distance_metric;
+        let unit_class_c = ctx.accounts.unit.components.get(&reference.troop_class).unwrap();
+        let unit_class = ComponentTroopClass::try_from_slice(&unit_class_c.data.as_slice()).unwrap().class;
+        let mut cost: u64 = 0;
+        let mut last_location = from_location;
+        let mut visited = vec![ctx.accounts.from.entity_id, ctx.accounts.to.entity_id];
+        let mut path = ctx.remaining_accounts.iter();
+        while let Some(tile_info) = path.next() {
+            let tile:Account<Entity> = Account::try_from(tile_info)?;
+            if tile.instance != ctx.accounts.registry_instance.instance || !ctx.accounts.instance_index.tiles.contains(&tile.entity_id) || visited.contains(&tile.entity_id) {
+                return err!(DominariError::InvalidAccounts)
+            }
+            visited.push(tile.entity_id);
+            let location_c = tile.components.get(&reference.location).unwrap();
+            let location = ComponentLocation::try_from_slice(&location_c.data.as_slice()).unwrap();
+            cost += get_step_cost(metric, &last_location, &location, &get_terrain(&tile, reference), &unit_class)?;
+
+            // Can move through friendly Units but not enemies
+            let occupant_c = tile.components.get(&reference.occupant).unwrap();
+            let occupant = ComponentOccupant::try_from_slice(&occupant_c.data.as_slice()).unwrap();
+            if occupant.occupant_id.is_some() {
+                let occupant_info = match path.next() {
+                    Some(occupant_info) => occupant_info,
+                    None => return err!(DominariError::InvalidAccounts)
+                };
+                let occupying_unit:Account<Entity> = Account::try_from(occupant_info)?;
+                if occupying_unit.instance != ctx.accounts.registry_instance.instance || Some(occupying_unit.entity_id) != occupant.occupant_id {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                let occupying_owner_c = occupying_unit.components.get(&reference.owner).unwrap();
+                let occupying_owner = ComponentOwner::try_from_slice(&occupying_owner_c.data.as_slice()).unwrap();
+                if !occupying_owner.is_friendly(&unit_owner) {
+                    return err!(ComponentErrors::EnemyInPath)
+                }
+            }
+            last_location = location;
+        }
+        cost += get_step_cost(metric, &last_location, &to_location, &get_terrain(&ctx.accounts.to, reference), &unit_class)?;
+
         let unit_range_component = ctx.accounts.unit.components.get(&reference.range).unwrap();
         let unit_range = ComponentRange::try_from_slice(&unit_range_component.data.as_slice()).unwrap();
-        if unit_range.movement < distance as u8 {
+        if cost > unit_range.movement as u64 * MOVEMENT_SCALE {
             return err!(ComponentErrors::UnitLacksMovement)
         }
 
@@ -644,7 +1138,10 @@ pub mod dominari {
         Ok(())
     }
     
-    pub fn attack_tile(ctx:Context<AttackTile>) -> Result<()> {
+    /**
+     * Remaining Accounts: [Defending Player] when the game has counter attacks and the defender belongs to a player
+     */
+    pub fn attack_tile<'info>(ctx:Context<'_, '_, '_, 'info, AttackTile<'info>>, reveal: [u8; 32]) -> Result<()> {
         // Attacker could be Feature or Unit (just needs Damage Component)
         let attacker = &ctx.accounts.attacker;
         let defender = &ctx.accounts.defender;
@@ -661,6 +1158,12 @@ pub mod dominari {
         if attacker_owner.owner != Some(ctx.accounts.payer.key()) {
             return err!(ComponentErrors::InvalidOwner)
         }
+
+        // Check that the attacking player is the attacker's owner
+        if attacker_owner.player != Some(ctx.accounts.attacking_player.entity_id) {
+            return err!(DominariError::InvalidAccounts)
+        }
+        ctx.accounts.instance_index.check_turn(attacker_owner.player)?;
         
         // Check that attacker is active
         let attacker_active_c = attacker.components.get(&reference.active).unwrap();
@@ -669,13 +1172,27 @@ pub mod dominari {
             return err!(ComponentErrors::UnitDead)
         }
 
-        // Check that defender is NOT owned by Payer
+        // Check that defender is NOT owned by Payer or a teammate
         let defender_owner_c = defender.components.get(&reference.owner).unwrap();
         let defender_owner = ComponentOwner::try_from_slice(&defender_owner_c.data.as_slice()).unwrap();
-        if defender_owner.player == attacker_owner.player {
+        if defender_owner.is_friendly(&attacker_owner) {
             return err!(ComponentErrors::FriendlyFire)
         }
 
+        // Defending player is credited if their counter attack kills the attacker
+        // Required up front so an attacker can't dodge a fatal counter attack by leaving it out
+        let mut defending_player: Option<Account<Entity>> = None;
+        if ctx.accounts.instance_index.config.counter_attacks && defender_owner.player.is_some() {
+            if ctx.remaining_accounts.len() < 1 {
+                return err!(DominariError::InvalidAccounts)
+            }
+            let player: Account<Entity> = Account::try_from(&ctx.remaining_accounts[0])?;
+            if player.instance != ctx.accounts.registry_instance.instance || Some(player.entity_id) != defender_owner.player {
+                return err!(DominariError::InvalidAccounts)
+            }
+            defending_player = Some(player);
+        }
+
         // Check attacker has damage component
         let attacker_damage_c = attacker.components.get(&reference.damage).unwrap();
         let attacker_damage = ComponentDamage::try_from_slice(&attacker_damage_c.data.as_slice()).unwrap();
@@ -683,17 +1200,15 @@ pub mod dominari {
         // Check defender is active and has health component
         
         let defender_active_c = defender.components.get(&reference.active).unwrap();
-        let mut defender_active = ComponentActive::try_from_slice(&defender_active_c.data.as_slice()).unwrap();
+        let defender_active = ComponentActive::try_from_slice(&defender_active_c.data.as_slice()).unwrap();
         if defender_active.active == false {
             return err!(ComponentErrors::UnitDead)
         }
-        let defender_health_c = defender.components.get(&reference.health);
-        if defender_health_c.is_none() {
+        if defender.components.get(&reference.health).is_none() {
             return err!(ComponentErrors::NoHealthComponent)
         }
-        let mut defender_health = ComponentHealth::try_from_slice(&defender_health_c.unwrap().data.as_slice()).unwrap();
 
-        // Defender must be in Range of Attacker
+        // Defender must be in Range of Attacker, range is straight line distance whatever the game's distance metric
         let attacker_location_c = attacker.components.get(&reference.location).unwrap();
         let attacker_location = ComponentLocation::try_from_slice(&attacker_location_c.data.as_slice()).unwrap();
         let defender_location_c = defender.components.get(&reference.location).unwrap();
@@ -735,105 +1250,81 @@ pub mod dominari {
         );
         registry::cpi::req_modify_component(modify_attacker_ctx, vec![(reference.last_used.key(),attacker_last_used.try_to_vec().unwrap())])?;
 
-        // Roll Damage for Attacker, apply modifiers 
-        let mut dmg = get_random_u64(attacker_damage.max_damage); 
-        
-        // check if defender is Feature, if not, look for it's TroopClass
-        let defender_metadata_c = defender.components.get(&reference.metadata).unwrap();
-        let defender_metadata = ComponentMetadata::try_from_slice(&defender_metadata_c.data.as_slice()).unwrap();
+        // Defending Tile gives the defender its cover, so it has to be the one it's on
+        check_on_tile(defender, &ctx.accounts.defending_tile, reference)?;
 
-        if defender_metadata.entity_type == EntityType::Feature {
-            dmg += attacker_damage.bonus_feature as u64;
-        } else {
-            let defender_troop_class_c = defender.components.get(&reference.troop_class).unwrap();
-            let defender_troop_class = ComponentTroopClass::try_from_slice(&defender_troop_class_c.data.as_slice()).unwrap();
-            match defender_troop_class.class {
-                TroopClass::Aircraft => dmg += attacker_damage.bonus_aircraft as u64,
-                TroopClass::Infantry => dmg += attacker_damage.bonus_infantry as u64,
-                TroopClass::Armor => dmg += attacker_damage.bonus_armor as u64,
-            }
-        }
+        // Roll Damage for Attacker with the Payer's revealed commitment, apply modifiers 
+        let mut oracle = load_oracle(&mut ctx.accounts.commitment, &ctx.accounts.slot_hashes, reveal, attacker.entity_id, defender.entity_id)?;
+        let dmg = roll_damage(oracle.as_mut(), &attacker_damage, defender, &ctx.accounts.defending_tile, reference);
 
-        if dmg < attacker_damage.min_damage {
-            dmg = attacker_damage.min_damage;
+        // Modify defender health
+            // If defender health at 0, Modify active as well and clear it off the defending tile
+        let registry_cpi = RegistryCpi {
+            registry_program: ctx.accounts.registry_program.to_account_info(),
+            registry_config: ctx.accounts.registry_config.to_account_info(),
+            action_bundle: ctx.accounts.config.to_account_info(),
+            action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+            core_ds: ctx.accounts.coreds.to_account_info(),
+            signer_seeds
+        };
+        let killed = apply_damage(&registry_cpi, reference, defender, &ctx.accounts.defending_tile, dmg)?;
+
+        // Credit the attacking player with the kill and the defender's value
+        if killed {
+            record_death(&mut ctx.accounts.instance_index, reference, defender);
+            let value = credit_kill(&registry_cpi, reference, &mut ctx.accounts.instance_index, &ctx.accounts.attacking_player, defender)?;
+            emit!(UnitKilled {
+                instance: ctx.accounts.registry_instance.instance,
+                attacker: attacker.entity_id,
+                defender: defender.entity_id,
+                player: ctx.accounts.attacking_player.entity_id,
+                score: value
+            });
         }
 
-        if dmg >= defender_health.health {
-            defender_health.health = 0;
-            defender_active.active = false;
-
-            // Modify the defending tile to remove the defender
-            let defending_tile = &ctx.accounts.defending_tile;
-            // Require Defender Location and Defending Tile Location are the same
-            let defending_tile_loc_c = defending_tile.components.get(&reference.location).unwrap();
-            let defending_tile_loc = ComponentLocation::try_from_slice(&defending_tile_loc_c.data.as_slice()).unwrap();
-            if defending_tile_loc.x != defender_location.x && defending_tile_loc.y != defender_location.y {
-                return err!(ComponentErrors::InvalidLocation)
+        // Surviving defender strikes back if it can do damage and has the attacker in range
+        let mut counter_dmg = None;
+        let defender_damage_c = defender.components.get(&reference.damage);
+        let defender_range_c = defender.components.get(&reference.range);
+        if ctx.accounts.instance_index.config.counter_attacks && !killed && defender_damage_c.is_some() && defender_range_c.is_some() && attacker.components.get(&reference.health).is_some() {
+            let defender_damage = ComponentDamage::try_from_slice(&defender_damage_c.unwrap().data.as_slice()).unwrap();
+            let defender_range = ComponentRange::try_from_slice(&defender_range_c.unwrap().data.as_slice()).unwrap();
+            if distance as u8 <= defender_range.attack_range {
+                // Attacking tile decides the attacker's cover, so it has to be the one it stands on
+                let attacking_tile = &ctx.accounts.attacking_tile;
+                check_on_tile(attacker, attacking_tile, reference)?;
+
+                // Same roll as the attack, class bonuses and cover included
+                let dmg_back = roll_damage(oracle.as_mut(), &defender_damage, attacker, attacking_tile, reference);
+                let attacker_killed = apply_damage(&registry_cpi, reference, attacker, attacking_tile, dmg_back)?;
+                counter_dmg = Some(dmg_back);
+                if attacker_killed {
+                    record_death(&mut ctx.accounts.instance_index, reference, attacker);
+                }
+
+                // Credit the defending player with the kill the same way
+                if attacker_killed && defending_player.is_some() {
+                    let defending_player = defending_player.as_ref().unwrap();
+                    let value = credit_kill(&registry_cpi, reference, &mut ctx.accounts.instance_index, defending_player, attacker)?;
+                    emit!(UnitKilled {
+                        instance: ctx.accounts.registry_instance.instance,
+                        attacker: defender.entity_id,
+                        defender: attacker.entity_id,
+                        player: defending_player.entity_id,
+                        score: value
+                    });
+                }
             }
-
-            if defender_metadata.entity_type == EntityType::Feature {
-                let tile_feature_c = defending_tile.components.get(&reference.feature).unwrap();
-                let mut tile_feature = ComponentFeature::try_from_slice(&tile_feature_c.data.as_slice()).unwrap();
-                tile_feature.feature_id = None;
-                let modify_tile_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.registry_program.to_account_info(),
-                    registry::cpi::accounts::ModifyComponent {
-                        registry_config: ctx.accounts.registry_config.to_account_info(),
-                        entity: ctx.accounts.defending_tile.to_account_info(),
-                        action_bundle: ctx.accounts.config.to_account_info(),
-                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
-                        core_ds: ctx.accounts.coreds.to_account_info(),
-                    },
-                    signer_seeds
-                );
-                registry::cpi::req_modify_component(modify_tile_ctx, vec![(reference.feature.key(), tile_feature.try_to_vec().unwrap())])?;
-
-            } else {
-                let tile_occupant_c = defending_tile.components.get(&reference.occupant).unwrap();
-                let mut tile_occupant = ComponentOccupant::try_from_slice(&tile_occupant_c.data.as_slice()).unwrap();
-                tile_occupant.occupant_id = None;
-                let modify_tile_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.registry_program.to_account_info(),
-                    registry::cpi::accounts::ModifyComponent {
-                        registry_config: ctx.accounts.registry_config.to_account_info(),
-                        entity: ctx.accounts.defending_tile.to_account_info(),
-                        action_bundle: ctx.accounts.config.to_account_info(),
-                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
-                        core_ds: ctx.accounts.coreds.to_account_info(),
-                    },
-                    signer_seeds
-                );
-                registry::cpi::req_modify_component(modify_tile_ctx, vec![(reference.occupant.key(),tile_occupant.try_to_vec().unwrap())])?;
-            }
-        } else {
-            defender_health.health -= dmg;
         }
 
-        // Modify defender health
-            // If defender health at 0, Modify active as well
-        let modify_defender_ctx = CpiContext::new_with_signer(
-            ctx.accounts.registry_program.to_account_info(),
-            registry::cpi::accounts::ModifyComponent {
-                registry_config: ctx.accounts.registry_config.to_account_info(),
-                entity: ctx.accounts.defender.to_account_info(),
-                action_bundle: ctx.accounts.config.to_account_info(),
-                action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
-                core_ds: ctx.accounts.coreds.to_account_info(),
-            },
-            signer_seeds
-        );
-        registry::cpi::req_modify_component(modify_defender_ctx, vec![
-                (reference.health.key(), defender_health.try_to_vec().unwrap()),
-                (reference.active.key(), defender_active.try_to_vec().unwrap()),
-            ])?;
-    
-
         emit!(TileAttacked{
             instance: ctx.accounts.registry_instance.instance,
             attacker: attacker.entity_id,
+            attacking_tile: ctx.accounts.attacking_tile.entity_id,
             defender: defender.entity_id,
             defending_tile: ctx.accounts.defending_tile.entity_id,
-            damage: dmg
+            damage: dmg,
+            counter_damage: counter_dmg
         });
 
         Ok(())
@@ -841,7 +1332,10 @@ pub mod dominari {
     //pub fn modify_unit(ctx:Context<ModUnit>) -> Result<()> {}
     
     //pub fn use_[feature](ctx:Context<UseFeature>) -> Result<()> {}
-    pub fn use_feature(ctx:Context<UseFeature>, use_feature_type: UseFeatureType) -> Result<()> {
+    /**
+     * Loot and Attack roll with the Payer's revealed commitment
+     */
+    pub fn use_feature<'info>(ctx:Context<'_, '_, '_, 'info, UseFeature<'info>>, use_feature_type: UseFeatureType, reveal: Option<[u8; 32]>) -> Result<()> {
         let reference = &ctx.accounts.config.components;
         let config_seeds:&[&[u8]] = &[
             SEEDS_ABSIGNER,
@@ -866,6 +1360,11 @@ pub mod dominari {
             return err!(DominariError::InvalidAccounts)
         }
 
+        // Check it's the turn of the Unit's player
+        let unit_owner_component = ctx.accounts.unit.components.get(&reference.owner).unwrap();
+        let unit_player = ComponentOwner::try_from_slice(&unit_owner_component.data.as_slice()).unwrap().player;
+        ctx.accounts.instance_index.check_turn(unit_player)?;
+
 
         // Match FeatureUse type with what components to fetch
         match use_feature_type {
@@ -895,29 +1394,1718 @@ pub mod dominari {
                 );
                 registry::cpi::req_modify_component(modify_health_ctx, vec![(reference.health.key(), health.try_to_vec().unwrap())])?;
             },
-            _=> {
-                msg!("That feature type is not implemented yet!")
+            UseFeatureType::Portal => {
+                // Remaining Accounts: [Destination Tile, Destination Portal Feature]
+                if ctx.remaining_accounts.len() < 2 {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                let target_tile_info = ctx.remaining_accounts[0].clone();
+                let target_feature_info = ctx.remaining_accounts[1].clone();
+                let target_tile:Account<Entity> = Account::try_from(&target_tile_info)?;
+                let target_feature:Account<Entity> = Account::try_from(&target_feature_info)?;
+                if target_tile.instance != ctx.accounts.registry_instance.instance || target_feature.instance != ctx.accounts.registry_instance.instance {
+                    return err!(DominariError::InvalidAccounts)
+                }
+
+                // Unit must be Owned by Player, active and off recovery
+                let unit_owner_component = ctx.accounts.unit.components.get(&reference.owner).unwrap();
+                let unit_owner = ComponentOwner::try_from_slice(&unit_owner_component.data.as_slice()).unwrap();
+                if unit_owner.owner.unwrap() != ctx.accounts.payer.key() {
+                    return err!(ComponentErrors::InvalidOwner)
+                }
+                let unit_active_component = ctx.accounts.unit.components.get(&reference.active).unwrap();
+                let unit_active = ComponentActive::try_from_slice(&unit_active_component.data.as_slice()).unwrap();
+                if unit_active.active == false {
+                    return err!(ComponentErrors::UnitDead)
+                }
+                let clock = Clock::get().unwrap();
+                let unit_last_used_component = ctx.accounts.unit.components.get(&reference.last_used).unwrap();
+                let mut unit_last_used = ComponentLastUsed::try_from_slice(&unit_last_used_component.data.as_slice()).unwrap();
+                if unit_last_used.last_used != 0 && (unit_last_used.last_used + unit_last_used.recovery) >= clock.slot {
+                    return err!(ComponentErrors::UnitRecovering)
+                }
+                unit_last_used.last_used = clock.slot;
+
+                // Destination Tile must hold the Destination Portal and be Empty
+                let target_feature_component = target_tile.components.get(&reference.feature).unwrap();
+                let target_tile_feature = ComponentFeature::try_from_slice(&target_feature_component.data.as_slice()).unwrap();
+                if target_tile_feature.feature_id != Some(target_feature.entity_id) || target_tile.entity_id == ctx.accounts.tile.entity_id {
+                    return err!(ComponentErrors::InvalidPortal)
+                }
+                let target_occupant_component = target_tile.components.get(&reference.occupant).unwrap();
+                let mut target_occupant = ComponentOccupant::try_from_slice(&target_occupant_component.data.as_slice()).unwrap();
+                if target_occupant.occupant_id.is_some() {
+                    return err!(ComponentErrors::TileOccupied)
+                }
+
+                // Portals are linked if they're on the same network and have the same Owner
+                // Upgrades rename Features, so the network is what ties them together
+                let portal_c = ctx.accounts.feature.components.get(&reference.portal);
+                let target_portal_c = target_feature.components.get(&reference.portal);
+                if portal_c.is_none() || target_portal_c.is_none() {
+                    return err!(ComponentErrors::InvalidPortal)
+                }
+                let portal = ComponentPortal::try_from_slice(&portal_c.unwrap().data.as_slice()).unwrap();
+                let target_portal = ComponentPortal::try_from_slice(&target_portal_c.unwrap().data.as_slice()).unwrap();
+                let portal_owner = ComponentOwner::try_from_slice(&ctx.accounts.feature.components.get(&reference.owner).unwrap().data.as_slice()).unwrap();
+                let target_owner = ComponentOwner::try_from_slice(&target_feature.components.get(&reference.owner).unwrap().data.as_slice()).unwrap();
+                if portal.network != target_portal.network || portal_owner.owner != target_owner.owner {
+                    return err!(ComponentErrors::InvalidPortal)
+                }
+
+                // Destination Portal must still be standing
+                let target_active = ComponentActive::try_from_slice(&target_feature.components.get(&reference.active).unwrap().data.as_slice()).unwrap();
+                if target_active.active == false {
+                    return err!(ComponentErrors::InvalidPortal)
+                }
+
+                // Check the Portal's cooldown and uses, then charge it
+                let portal_updates = charge_feature_use(&ctx.accounts.feature, reference, clock.slot)?;
+                if portal_updates.len() > 0 {
+                    let modify_portal_ctx = CpiContext::new_with_signer(
+                        ctx.accounts.registry_program.to_account_info(),
+                        registry::cpi::accounts::ModifyComponent {
+                            registry_config: ctx.accounts.registry_config.to_account_info(),
+                            entity: ctx.accounts.feature.to_account_info(),
+                            action_bundle: ctx.accounts.config.to_account_info(),
+                            action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                            core_ds: ctx.accounts.coreds.to_account_info(),
+                        },
+                        signer_seeds
+                    );
+                    registry::cpi::req_modify_component(modify_portal_ctx, portal_updates)?;
+                }
+
+                // Move the Unit's Location to the Destination Tile and start its recovery
+                let target_location_component = target_tile.components.get(&reference.location).unwrap();
+                let modify_unit_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.registry_program.to_account_info(),
+                    registry::cpi::accounts::ModifyComponent {
+                        registry_config: ctx.accounts.registry_config.to_account_info(),
+                        entity: ctx.accounts.unit.to_account_info(),
+                        action_bundle: ctx.accounts.config.to_account_info(),
+                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                        core_ds: ctx.accounts.coreds.to_account_info(),
+                    },
+                    signer_seeds
+                );
+                registry::cpi::req_modify_component(modify_unit_ctx, vec![
+                    (reference.location.key(), target_location_component.data.clone()),
+                    (reference.last_used.key(), unit_last_used.try_to_vec().unwrap()),
+                ])?;
+
+                // Modify Source Tile Occupant to be None
+                let mut source_occupant = occupant.clone();
+                source_occupant.occupant_id = None;
+                let modify_from_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.registry_program.to_account_info(),
+                    registry::cpi::accounts::ModifyComponent {
+                        registry_config: ctx.accounts.registry_config.to_account_info(),
+                        entity: ctx.accounts.tile.to_account_info(),
+                        action_bundle: ctx.accounts.config.to_account_info(),
+                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                        core_ds: ctx.accounts.coreds.to_account_info(),
+                    },
+                    signer_seeds
+                );
+                registry::cpi::req_modify_component(modify_from_ctx, vec![(reference.occupant.key(), source_occupant.try_to_vec().unwrap())])?;
+
+                // Modify Destination Tile Occupant to be Unit
+                target_occupant.occupant_id = Some(ctx.accounts.unit.entity_id);
+                let modify_to_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.registry_program.to_account_info(),
+                    registry::cpi::accounts::ModifyComponent {
+                        registry_config: ctx.accounts.registry_config.to_account_info(),
+                        entity: target_tile_info,
+                        action_bundle: ctx.accounts.config.to_account_info(),
+                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                        core_ds: ctx.accounts.coreds.to_account_info(),
+                    },
+                    signer_seeds
+                );
+                registry::cpi::req_modify_component(modify_to_ctx, vec![(reference.occupant.key(), target_occupant.try_to_vec().unwrap())])?;
+
+                emit!(TroopMovement {
+                    instance: ctx.accounts.registry_instance.instance,
+                    from: ctx.accounts.tile.entity_id,
+                    to: target_tile.entity_id,
+                    unit: ctx.accounts.unit.entity_id
+                });
+            },
+            UseFeatureType::Loot => {
+                // Remaining Accounts: [Player Entity that owns the Unit, Commitment, SlotHashes]
+                if ctx.remaining_accounts.len() < 3 {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                let player_info = ctx.remaining_accounts[0].clone();
+                let player:Account<Entity> = Account::try_from(&player_info)?;
+                if player.instance != ctx.accounts.registry_instance.instance {
+                    return err!(DominariError::InvalidAccounts)
+                }
+
+                // Unit must be Owned by Player
+                let unit_owner_component = ctx.accounts.unit.components.get(&reference.owner).unwrap();
+                let unit_owner = ComponentOwner::try_from_slice(&unit_owner_component.data.as_slice()).unwrap();
+                if unit_owner.owner.unwrap() != ctx.accounts.payer.key() || unit_owner.player != Some(player.entity_id) {
+                    return err!(ComponentErrors::InvalidOwner)
+                }
+
+                let player_stats_component = player.components.get(&reference.player_stats).unwrap();
+                let mut player_stats = ComponentPlayerStats::try_from_slice(&player_stats_component.data.as_slice()).unwrap();
+                if player_stats.cards.len() as u64 >= PLAYER_MAX_CARDS {
+                    return err!(ComponentErrors::HandFull)
+                }
+
+                // Grab the Drop Table from the Feature
+                let drop_table_component = ctx.accounts.feature.components.get(&reference.drop_table);
+                if drop_table_component.is_none() {
+                    return err!(ComponentErrors::EmptyDropTable)
+                }
+                let drop_table = ComponentDropTable::try_from_slice(&drop_table_component.unwrap().data.as_slice()).unwrap();
+                if drop_table.drop_table.len() == 0 {
+                    return err!(ComponentErrors::EmptyDropTable)
+                }
+
+                // Check the Feature's cooldown and uses, then charge it
+                let clock = Clock::get().unwrap();
+                let loot_updates = charge_feature_use(&ctx.accounts.feature, reference, clock.slot)?;
+                if loot_updates.len() > 0 {
+                    let modify_feature_ctx = CpiContext::new_with_signer(
+                        ctx.accounts.registry_program.to_account_info(),
+                        registry::cpi::accounts::ModifyComponent {
+                            registry_config: ctx.accounts.registry_config.to_account_info(),
+                            entity: ctx.accounts.feature.to_account_info(),
+                            action_bundle: ctx.accounts.config.to_account_info(),
+                            action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                            core_ds: ctx.accounts.coreds.to_account_info(),
+                        },
+                        signer_seeds
+                    );
+                    registry::cpi::req_modify_component(modify_feature_ctx, loot_updates)?;
+                }
+
+                // Roll against the Drop Table and add the Blueprint to the Player's Hand
+                let mut oracle = load_oracle_from_accounts(
+                    &ctx.remaining_accounts[1],
+                    &ctx.remaining_accounts[2],
+                    ctx.accounts.payer.key(),
+                    ctx.accounts.registry_instance.instance,
+                    reveal,
+                    ctx.accounts.unit.entity_id,
+                    ctx.accounts.feature.entity_id
+                )?;
+                let roll = oracle.get_random_u64(drop_table.drop_table.len() as u64);
+                let drop = drop_table.drop_table[roll as usize];
+                player_stats.cards.push(drop);
+
+                let modify_player_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.registry_program.to_account_info(),
+                    registry::cpi::accounts::ModifyComponent {
+                        registry_config: ctx.accounts.registry_config.to_account_info(),
+                        entity: player_info,
+                        action_bundle: ctx.accounts.config.to_account_info(),
+                        action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                        core_ds: ctx.accounts.coreds.to_account_info(),
+                    },
+                    signer_seeds
+                );
+                registry::cpi::req_modify_component(modify_player_ctx, vec![(reference.player_stats.key(), player_stats.try_to_vec().unwrap())])?;
+
+                emit!(FeatureLooted {
+                    instance: ctx.accounts.registry_instance.instance,
+                    feature: ctx.accounts.feature.entity_id,
+                    player: player.entity_id,
+                    blueprint: drop
+                });
+            },
+            UseFeatureType::Attack => {
+                // Remaining Accounts: [Player Entity that owns the Unit, Defender, Defending Tile, Commitment, SlotHashes]
+                if ctx.remaining_accounts.len() < 5 {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                let player:Account<Entity> = Account::try_from(&ctx.remaining_accounts[0])?;
+                let defender:Account<Entity> = Account::try_from(&ctx.remaining_accounts[1])?;
+                let defending_tile:Account<Entity> = Account::try_from(&ctx.remaining_accounts[2])?;
+                if player.instance != ctx.accounts.registry_instance.instance || defender.instance != ctx.accounts.registry_instance.instance || defending_tile.instance != ctx.accounts.registry_instance.instance {
+                    return err!(DominariError::InvalidAccounts)
+                }
+
+                // Garrisoned Unit must be owned by Payer and Player, active and off recovery
+                let unit_owner_component = ctx.accounts.unit.components.get(&reference.owner).unwrap();
+                let unit_owner = ComponentOwner::try_from_slice(&unit_owner_component.data.as_slice()).unwrap();
+                if unit_owner.owner != Some(ctx.accounts.payer.key()) || unit_owner.player != Some(player.entity_id) {
+                    return err!(ComponentErrors::InvalidOwner)
+                }
+                let unit_active_component = ctx.accounts.unit.components.get(&reference.active).unwrap();
+                let unit_active = ComponentActive::try_from_slice(&unit_active_component.data.as_slice()).unwrap();
+                if unit_active.active == false {
+                    return err!(ComponentErrors::UnitDead)
+                }
+                let clock = Clock::get().unwrap();
+                let unit_last_used_component = ctx.accounts.unit.components.get(&reference.last_used).unwrap();
+                let mut unit_last_used = ComponentLastUsed::try_from_slice(&unit_last_used_component.data.as_slice()).unwrap();
+                if unit_last_used.last_used != 0 && (unit_last_used.last_used + unit_last_used.recovery) >= clock.slot {
+                    return err!(ComponentErrors::UnitRecovering)
+                }
+                unit_last_used.last_used = clock.slot;
+
+                // Only the Feature's player (or their teammates) can fire it, a Unit can't turn an enemy's Feature on its owner
+                let feature_owner_component = ctx.accounts.feature.components.get(&reference.owner).unwrap();
+                let feature_owner = ComponentOwner::try_from_slice(&feature_owner_component.data.as_slice()).unwrap();
+                if !feature_owner.is_friendly(&unit_owner) {
+                    return err!(ComponentErrors::InvalidOwner)
+                }
+
+                // Feature must be active and armed
+                let feature_active_component = ctx.accounts.feature.components.get(&reference.active).unwrap();
+                let feature_active = ComponentActive::try_from_slice(&feature_active_component.data.as_slice()).unwrap();
+                if feature_active.active == false {
+                    return err!(ComponentErrors::UnitDead)
+                }
+                let feature_damage_component = ctx.accounts.feature.components.get(&reference.damage);
+                let feature_range_component = ctx.accounts.feature.components.get(&reference.range);
+                if feature_damage_component.is_none() || feature_range_component.is_none() {
+                    return err!(ComponentErrors::FeatureUnarmed)
+                }
+                let feature_damage = ComponentDamage::try_from_slice(&feature_damage_component.unwrap().data.as_slice()).unwrap();
+                let feature_range = ComponentRange::try_from_slice(&feature_range_component.unwrap().data.as_slice()).unwrap();
+
+                // Check that defender is NOT owned by the garrisoned Unit's Player or a teammate
+                let defender_owner_c = defender.components.get(&reference.owner).unwrap();
+                let defender_owner = ComponentOwner::try_from_slice(&defender_owner_c.data.as_slice()).unwrap();
+                if defender_owner.is_friendly(&unit_owner) {
+                    return err!(ComponentErrors::FriendlyFire)
+                }
+
+                // Check defender is active
+                let defender_active_c = defender.components.get(&reference.active).unwrap();
+                let defender_active = ComponentActive::try_from_slice(&defender_active_c.data.as_slice()).unwrap();
+                if defender_active.active == false {
+                    return err!(ComponentErrors::UnitDead)
+                }
+
+                // Defender must be in Range of the Feature
+                let feature_location_c = ctx.accounts.feature.components.get(&reference.location).unwrap();
+                let feature_location = ComponentLocation::try_from_slice(&feature_location_c.data.as_slice()).unwrap();
+                let defender_location_c = defender.components.get(&reference.location).unwrap();
+                let defender_location = ComponentLocation::try_from_slice(&defender_location_c.data.as_slice()).unwrap();
+                let distance:f64 = (((defender_location.x as f64 - feature_location.x as f64).powf(2_f64) + (defender_location.y as f64 - feature_location.y as f64 ).powf(2_f64)) as f64).sqrt();
+                if distance as u8 > feature_range.attack_range {
+                    return err!(ComponentErrors::OutOfRange)
+                }
+
+                let registry_cpi = RegistryCpi {
+                    registry_program: ctx.accounts.registry_program.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                    signer_seeds
+                };
+
+                // Check the Feature's cooldown and uses, then charge it
+                // The garrisoned Unit starts its recovery too, so it can't fire the Feature and attack in the same slot
+                let feature_updates = charge_feature_use(&ctx.accounts.feature, reference, clock.slot)?;
+                if feature_updates.len() > 0 {
+                    registry_cpi.modify_component(ctx.accounts.feature.to_account_info(), feature_updates)?;
+                }
+                registry_cpi.modify_component(ctx.accounts.unit.to_account_info(), vec![(reference.last_used.key(), unit_last_used.try_to_vec().unwrap())])?;
+
+                // Roll the Feature's Damage and apply it to the Defender, the Defending Tile gives it cover
+                check_on_tile(&defender, &defending_tile, reference)?;
+                let mut oracle = load_oracle_from_accounts(
+                    &ctx.remaining_accounts[3],
+                    &ctx.remaining_accounts[4],
+                    ctx.accounts.payer.key(),
+                    ctx.accounts.registry_instance.instance,
+                    reveal,
+                    ctx.accounts.feature.entity_id,
+                    defender.entity_id
+                )?;
+                let dmg = roll_damage(oracle.as_mut(), &feature_damage, &defender, &defending_tile, reference);
+                let killed = apply_damage(&registry_cpi, reference, &defender, &defending_tile, dmg)?;
+
+                // Credit the garrisoned Unit's Player with the kill, same as attack_tile
+                if killed {
+                    record_death(&mut ctx.accounts.instance_index, reference, &defender);
+                    let value = credit_kill(&registry_cpi, reference, &mut ctx.accounts.instance_index, &player, &defender)?;
+                    emit!(UnitKilled {
+                        instance: ctx.accounts.registry_instance.instance,
+                        attacker: ctx.accounts.feature.entity_id,
+                        defender: defender.entity_id,
+                        player: player.entity_id,
+                        score: value
+                    });
+                }
+
+                emit!(TileAttacked{
+                    instance: ctx.accounts.registry_instance.instance,
+                    attacker: ctx.accounts.feature.entity_id,
+                    attacking_tile: ctx.accounts.tile.entity_id,
+                    defender: defender.entity_id,
+                    defending_tile: defending_tile.entity_id,
+                    damage: dmg,
+                    counter_damage: None
+                });
+            },
+        }
+        Ok(())
+    }
+
+    pub fn upgrade_feature(ctx:Context<UpgradeFeature>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+
+        // Features can be upgraded while building or playing
+        if ctx.accounts.instance_index.play_phase != PlayPhase::Play && ctx.accounts.instance_index.play_phase != PlayPhase::Build {
+            return err!(DominariError::GamePaused)
+        }
+
+        // Tile must be owned by Payer and hold the Feature
+        let tile_owner_component = ctx.accounts.tile.components.get(&reference.owner).unwrap();
+        let tile_owner = ComponentOwner::try_from_slice(&tile_owner_component.data.as_slice()).unwrap();
+        if tile_owner.owner != Some(ctx.accounts.payer.key()) {
+            return err!(ComponentErrors::InvalidOwner)
+        }
+        let tile_feature_component = ctx.accounts.tile.components.get(&reference.feature).unwrap();
+        let tile_feature = ComponentFeature::try_from_slice(&tile_feature_component.data.as_slice()).unwrap();
+        if tile_feature.feature_id != Some(ctx.accounts.feature.entity_id) {
+            return err!(DominariError::InvalidAccounts)
+        }
+
+        // Feature must have a Rank that isn't maxed out
+        let feature_rank_component = ctx.accounts.feature.components.get(&reference.feature_rank);
+        if feature_rank_component.is_none() {
+            return err!(ComponentErrors::FeatureNotUpgradeable)
+        }
+        let mut feature_rank = ComponentFeatureRank::try_from_slice(&feature_rank_component.unwrap().data.as_slice()).unwrap();
+        if feature_rank.rank >= feature_rank.max_rank {
+            return err!(ComponentErrors::FeatureMaxRank)
+        }
+        feature_rank.rank += 1;
+        let rank = feature_rank.rank as usize;
+
+        // Pay for the next rung of the ladder
+        let cost = *feature_rank.cost_for_use_ladder.get(rank).unwrap_or(&0);
+        if cost > 0 {
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                }
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, cost)?;
+
+            emit!(CostPaid {
+                instance: ctx.accounts.registry_instance.instance,
+                payer: ctx.accounts.payer.key(),
+                entity: ctx.accounts.feature.entity_id,
+                lamports: cost
+            });
+        }
+
+        let mut updates: Vec<(Pubkey, Vec<u8>)> = vec![];
+
+        // Name and Link come from the Rank ladders
+        let name = feature_rank.name_rank_ladder.get(rank);
+        if name.is_some() {
+            let metadata_component = ctx.accounts.feature.components.get(&reference.metadata).unwrap();
+            let mut metadata = ComponentMetadata::try_from_slice(&metadata_component.data.as_slice()).unwrap();
+            metadata.name = name.unwrap().clone();
+            updates.push((reference.metadata.key(), metadata.try_to_vec().unwrap()));
+        }
+
+        let link = feature_rank.link_rank_ladder.get(rank);
+        let offchain_metadata_component = ctx.accounts.feature.components.get(&reference.offchain_metadata);
+        if link.is_some() && offchain_metadata_component.is_some() {
+            let mut offchain_metadata = ComponentOffchainMetadata::try_from_slice(&offchain_metadata_component.unwrap().data.as_slice()).unwrap();
+            offchain_metadata.link = link.unwrap().clone();
+            updates.push((reference.offchain_metadata.key(), offchain_metadata.try_to_vec().unwrap()));
+        }
+
+        // Per Rank Stat Increase applies to whichever stats the Feature has
+        let increase = feature_rank.per_rank_stat_increase;
+        let healing_power_component = ctx.accounts.feature.components.get(&reference.healing_power);
+        if healing_power_component.is_some() {
+            let mut healing_power = ComponentHealingPower::try_from_slice(&healing_power_component.unwrap().data.as_slice()).unwrap();
+            healing_power.heals += increase;
+            updates.push((reference.healing_power.key(), healing_power.try_to_vec().unwrap()));
+        }
+
+        let damage_component = ctx.accounts.feature.components.get(&reference.damage);
+        if damage_component.is_some() {
+            let mut damage = ComponentDamage::try_from_slice(&damage_component.unwrap().data.as_slice()).unwrap();
+            damage.min_damage += increase;
+            damage.max_damage += increase;
+            updates.push((reference.damage.key(), damage.try_to_vec().unwrap()));
+        }
+
+        let health_component = ctx.accounts.feature.components.get(&reference.health);
+        if health_component.is_some() {
+            let mut health = ComponentHealth::try_from_slice(&health_component.unwrap().data.as_slice()).unwrap();
+            health.health += increase;
+            updates.push((reference.health.key(), health.try_to_vec().unwrap()));
+        }
+
+        updates.push((reference.feature_rank.key(), feature_rank.try_to_vec().unwrap()));
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+
+        let modify_feature_ctx = CpiContext::new_with_signer(
+            ctx.accounts.registry_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent {
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                entity: ctx.accounts.feature.to_account_info(),
+                action_bundle: ctx.accounts.config.to_account_info(),
+                action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                core_ds: ctx.accounts.coreds.to_account_info(),
+            },
+            signer_seeds
+        );
+        registry::cpi::req_modify_component(modify_feature_ctx, updates)?;
+
+        emit!(FeatureUpgraded {
+            instance: ctx.accounts.registry_instance.instance,
+            feature: ctx.accounts.feature.entity_id,
+            rank: feature_rank.rank,
+            cost
+        });
+        Ok(())
+    }
+
+    /**
+     * Unclaimed tiles are bought from the instance, paying the Tile's Cost into the Treasury
+     * Tiles owned by another player can only be bought if they've been listed, paying the Cost to the seller
+     * Pre-owned tiles are claimed for free by the wallet holding them, so they count as that player's territory
+     * A Feature on the Tile changes hands with it, so it has to be passed in as the only remaining account
+     * Buying takes the Tile off the market: its Cost is the asking price, so it's cleared rather than kept at the map's price
+     */
+    pub fn buy_tile<'info>(ctx:Context<'_, '_, '_, 'info, BuyTile<'info>>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+
+        if ctx.accounts.instance_index.play_phase != PlayPhase::Play && ctx.accounts.instance_index.play_phase != PlayPhase::Build {
+            return err!(DominariError::GamePaused)
+        }
+
+        // Player must be in the game and belong to Payer
+        if !ctx.accounts.instance_index.players.contains(&ctx.accounts.player.entity_id) {
+            return err!(DominariError::InvalidPlayer)
+        }
+        let player_stats_component = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_component.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.payer.key() {
+            return err!(ComponentErrors::InvalidOwner)
+        }
+
+        let tile_owner_component = ctx.accounts.tile.components.get(&reference.owner).unwrap();
+        let mut tile_owner = ComponentOwner::try_from_slice(&tile_owner_component.data.as_slice()).unwrap();
+        if tile_owner.player == Some(ctx.accounts.player.entity_id) {
+            return err!(ComponentErrors::TileAlreadyOwned)
+        }
+        let tile_cost_component = ctx.accounts.tile.components.get(&reference.cost).unwrap();
+        let mut tile_cost = ComponentCost::try_from_slice(&tile_cost_component.data.as_slice()).unwrap();
+        let mut price = tile_cost.lamports;
+
+        // Unclaimed Tiles are still held by the instance creator, anything else is a claim or a resale
+        if ctx.accounts.instance_index.is_unclaimed(&tile_owner) {
+            // Unclaimed, pay the Treasury
+            if price > 0 {
+                let transfer_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    }
+                );
+                anchor_lang::system_program::transfer(transfer_ctx, price)?;
+            }
+        } else if tile_owner.player.is_none() && tile_owner.owner == Some(ctx.accounts.payer.key()) {
+            // Pre-owned by Payer, they're only moving it onto their Player
+            price = 0;
+        } else {
+            // Resale, Tile must be listed and the seller must be the current owner
+            if price == 0 {
+                return err!(ComponentErrors::TileNotForSale)
+            }
+            if tile_owner.owner != Some(ctx.accounts.seller.key()) {
+                return err!(ComponentErrors::InvalidOwner)
+            }
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: ctx.accounts.seller.to_account_info(),
+                }
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, price)?;
+        }
+
+        // New owner has to list the tile again if they want to sell it
+        // Owned Tiles never go back to being unclaimed, so the map's price isn't needed again
+        tile_owner.owner = Some(ctx.accounts.payer.key());
+        tile_owner.player = Some(ctx.accounts.player.entity_id);
+        tile_owner.team = ctx.accounts.instance_index.get_team(ctx.accounts.player.entity_id);
+        tile_cost.lamports = 0;
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+
+        // Feature on the Tile goes to the new owner too, so whoever holds the Tile can upgrade and use it
+        let tile_feature_component = ctx.accounts.tile.components.get(&reference.feature).unwrap();
+        let tile_feature = ComponentFeature::try_from_slice(&tile_feature_component.data.as_slice()).unwrap();
+        if let Some(feature_id) = tile_feature.feature_id {
+            if ctx.remaining_accounts.len() < 1 {
+                return err!(DominariError::InvalidAccounts)
+            }
+            let feature_info = ctx.remaining_accounts[0].clone();
+            let feature:Account<Entity> = Account::try_from(&feature_info)?;
+            if feature.instance != ctx.accounts.registry_instance.instance || feature.entity_id != feature_id {
+                return err!(DominariError::InvalidAccounts)
+            }
+
+            let modify_feature_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::ModifyComponent {
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    entity: feature_info,
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::req_modify_component(modify_feature_ctx, vec![(reference.owner.key(), tile_owner.try_to_vec().unwrap())])?;
+        }
+
+        let modify_tile_ctx = CpiContext::new_with_signer(
+            ctx.accounts.registry_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent {
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                entity: ctx.accounts.tile.to_account_info(),
+                action_bundle: ctx.accounts.config.to_account_info(),
+                action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                core_ds: ctx.accounts.coreds.to_account_info(),
+            },
+            signer_seeds
+        );
+        registry::cpi::req_modify_component(modify_tile_ctx, vec![
+            (reference.owner.key(), tile_owner.try_to_vec().unwrap()),
+            (reference.cost.key(), tile_cost.try_to_vec().unwrap()),
+        ])?;
+
+        emit!(TileBought {
+            instance: ctx.accounts.registry_instance.instance,
+            tile: ctx.accounts.tile.entity_id,
+            player: ctx.accounts.player.entity_id,
+            price
+        });
+        Ok(())
+    }
+
+    /**
+     * Tile owners (players or pre-owned Tile holders) can put their tile up for sale, a price of 0 takes it off the market
+     * There's no way to give a tile away, a price of 0 always means it isn't for sale
+     */
+    pub fn list_tile(ctx:Context<ListTile>, price: u64) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+
+        let tile_owner_component = ctx.accounts.tile.components.get(&reference.owner).unwrap();
+        let tile_owner = ComponentOwner::try_from_slice(&tile_owner_component.data.as_slice()).unwrap();
+        // Unclaimed Tiles are priced by the map, not listed
+        if ctx.accounts.instance_index.is_unclaimed(&tile_owner) || tile_owner.owner != Some(ctx.accounts.payer.key()) {
+            return err!(ComponentErrors::InvalidOwner)
+        }
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+
+        let modify_tile_ctx = CpiContext::new_with_signer(
+            ctx.accounts.registry_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent {
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                entity: ctx.accounts.tile.to_account_info(),
+                action_bundle: ctx.accounts.config.to_account_info(),
+                action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                core_ds: ctx.accounts.coreds.to_account_info(),
+            },
+            signer_seeds
+        );
+        registry::cpi::req_modify_component(modify_tile_ctx, vec![(reference.cost.key(), ComponentCost { lamports: price }.try_to_vec().unwrap())])?;
+
+        emit!(TileListed {
+            instance: ctx.accounts.registry_instance.instance,
+            tile: ctx.accounts.tile.entity_id,
+            price
+        });
+        Ok(())
+    }
+
+    /**
+     * Players commit to hash(secret) and the actor and target it's for before any instruction that rolls, then reveal the secret in it
+     * The roll mixes the secret with the hash of the first slot after the commitment landed, and the newest slot hash when it's revealed
+     * An unused commitment can't be replaced until it expires, so a bad roll can't be dodged by committing again
+     */
+    pub fn commit_randomness(ctx:Context<CommitRandomness>, commitment: [u8; 32], actor: u64, target: u64) -> Result<()> {
+        let clock = Clock::get().unwrap();
+        let previous = &ctx.accounts.commitment;
+        if previous.commitment != [0; 32] && clock.slot <= previous.slot + anchor_lang::solana_program::slot_hashes::MAX_ENTRIES as u64 {
+            return err!(DominariError::CommitmentOutstanding)
+        }
+
+        ctx.accounts.commitment.player = ctx.accounts.payer.key();
+        ctx.accounts.commitment.instance = ctx.accounts.registry_instance.instance;
+        ctx.accounts.commitment.commitment = commitment;
+        ctx.accounts.commitment.slot = clock.slot;
+        ctx.accounts.commitment.actor = actor;
+        ctx.accounts.commitment.target = target;
+
+        emit!(RandomnessCommitted {
+            instance: ctx.accounts.registry_instance.instance,
+            player: ctx.accounts.payer.key(),
+            slot: clock.slot,
+            actor,
+            target
+        });
+        Ok(())
+    }
+
+    /**
+     * Pass in multiple entities through remaining accounts; will strip and close each one, refunding rent to the Benefactor
+     * Benefactor has to be whoever paid for every entity passed in: the owner of Troops and Features, the wallet behind a Player, and the instance creator for the Map and Tiles
+     * Can Close Troops if Troop Owner and the Troop is dead
+     * Can Close Features if Feature Owner or Leader and the Feature is destroyed
+     * Once the game is Finished, Leader can close Map, Tiles, and Features, and players can close their own Player and Troops
+     * Closing a Player pays out whatever they're still owed from the Escrow, see InstanceEscrow::owed
+     * Closing a Player takes everything the Instance Index holds for it
+     * Can Close *Instance Index* if it's Empty and the game is Finished, its rent goes back to the instance creator
+     */
+    pub fn reclaim_sol<'info>(ctx:Context<'_, '_, '_, 'info, ReclaimSol<'info>>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        let is_finished = ctx.accounts.instance_index.play_phase == PlayPhase::Finished;
+        let is_leader = ctx.accounts.instance_index.authority.key() == ctx.accounts.payer.key();
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+
+        let slot = Clock::get().unwrap().slot;
+        let creator = ctx.accounts.instance_index.creator;
+        let benefactor = ctx.accounts.benefactor.key();
+        let mut freed: usize = 0;
+        for entity_info in ctx.remaining_accounts.iter() {
+            let entity:Account<Entity> = Account::try_from(entity_info)?;
+            if entity.instance != ctx.accounts.registry_instance.instance {
+                return err!(DominariError::InvalidAccounts)
+            }
+
+            let metadata_c = entity.components.get(&reference.metadata).unwrap();
+            let metadata = ComponentMetadata::try_from_slice(&metadata_c.data.as_slice()).unwrap();
+            let is_dead = match entity.components.get(&reference.active) {
+                Some(active_c) => !ComponentActive::try_from_slice(&active_c.data.as_slice()).unwrap().active,
+                None => false
+            };
+            let owner = match entity.components.get(&reference.owner) {
+                Some(owner_c) => ComponentOwner::try_from_slice(&owner_c.data.as_slice()).unwrap().owner,
+                None => None
+            };
+            let is_owner = owner == Some(ctx.accounts.payer.key());
+            let mut prize: u64 = 0;
+
+            // Whether Payer can close it, and who paid for it
+            let (can_close, paid_by) = match metadata.entity_type {
+                EntityType::Unit => (is_owner && (is_dead || is_finished), owner),
+                EntityType::Feature => ((is_owner || is_leader) && (is_dead || is_finished), owner),
+                EntityType::Map | EntityType::Tile => (is_leader && is_finished, Some(creator)),
+                EntityType::Player => {
+                    let player_stats_c = entity.components.get(&reference.player_stats).unwrap();
+                    let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+                    if is_finished {
+                        prize = ctx.accounts.escrow.owed(entity.entity_id, &ctx.accounts.instance_index);
+                    }
+                    (player_stats.key.key() == ctx.accounts.payer.key() && is_finished, Some(player_stats.key.key()))
+                }
+            };
+            if !can_close {
+                return err!(DominariError::CannotReclaim)
+            }
+            if paid_by != Some(benefactor) {
+                return err!(DominariError::InvalidAccounts)
+            }
+
+            // A prize or refund the player never claimed goes out with the rent
+            if prize > 0 {
+                pay_from_escrow(&ctx.accounts.escrow, &ctx.accounts.benefactor, prize)?;
+                ctx.accounts.escrow.paid += prize;
+                ctx.accounts.escrow.claimed.push(entity.entity_id);
+                emit!(PrizeClaimed {
+                    instance: ctx.accounts.registry_instance.instance,
+                    player: entity.entity_id,
+                    amount: prize,
+                    refund: ctx.accounts.instance_index.abandoned
+                });
             }
+
+            // Strip the components, then close the now empty Entity
+            let remove_components_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::RemoveComponent {
+                    benefactor: ctx.accounts.benefactor.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    entity: entity_info.clone(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::req_remove_component(remove_components_ctx, entity.components.keys().cloned().collect())?;
+
+            let remove_entity_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::RemoveEntity {
+                    benefactor: ctx.accounts.benefactor.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    entity: entity_info.clone(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::req_remove_entity(remove_entity_ctx)?;
+
+            // Remove the Entity from the Instance Index
+            let index = &mut ctx.accounts.instance_index;
+            let id = entity.entity_id;
+            if metadata.entity_type == EntityType::Player {
+                // Players also have their vote, team, spawn zone, living Units and score to clear out
+                if index.players.contains(&id) {
+                    index.remove_player(id, slot);
+                    freed += PLAYER_INDEX_SIZE;
+                }
+            } else {
+                let list = match metadata.entity_type {
+                    EntityType::Unit => Some(&mut index.units),
+                    EntityType::Feature => Some(&mut index.features),
+                    EntityType::Tile => Some(&mut index.tiles),
+                    _ => None
+                };
+                if list.is_some() {
+                    let list = list.unwrap();
+                    let idx = list.iter().position(|&e| e == id);
+                    if idx.is_some() {
+                        list.remove(idx.unwrap());
+                        freed += ENTITY_ID_SIZE;
+                    }
+                }
+            }
+
+            emit!(EntityReclaimed {
+                instance: ctx.accounts.registry_instance.instance,
+                entity: id,
+                benefactor
+            });
+        }
+
+        let index = &ctx.accounts.instance_index;
+        let is_empty = index.tiles.len() == 0 && index.features.len() == 0 && index.units.len() == 0 && index.players.len() == 0;
+        if is_finished && is_leader && is_empty && benefactor == creator {
+            // Close the Instance Index entirely
+            ctx.accounts.instance_index.close(ctx.accounts.benefactor.to_account_info())?;
+        } else if freed > 0 {
+            shrink_instance_index(&ctx.accounts.instance_index.to_account_info(), &ctx.accounts.benefactor.to_account_info(), freed)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Players can leave any time before the game is Finished
+     * In the Lobby their Player entity is closed and the rent (and their room in the Instance Index) refunded
+     * After that they forfeit: their Units are deactivated and the Tiles under them freed
+     * Forfeiting needs the player's living Units as remaining accounts, each with the Tile under it right after it
+     * The entry fee is refunded to anyone leaving before Play
+     * If only one player is left, they win, unless the game hasn't got to Play yet and is abandoned instead
+     * Fewer players need fewer votes, so a pending phase vote can pass when someone leaves
+     */
+    pub fn leave_game<'info>(ctx:Context<'_, '_, '_, 'info, LeaveGame<'info>>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        let player_id = ctx.accounts.player.entity_id;
+        if ctx.accounts.instance_index.play_phase == PlayPhase::Finished {
+            return err!(DominariError::InvalidPlayPhase)
+        }
+        if !ctx.accounts.instance_index.players.contains(&player_id) {
+            return err!(DominariError::InvalidPlayer)
+        }
+        let player_stats_component = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_component.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.payer.key() {
+            return err!(ComponentErrors::InvalidOwner)
+        }
+
+        let config_seeds:&[&[u8]] = &[
+            SEEDS_ABSIGNER,
+            &[*ctx.bumps.get("config").unwrap()]
+        ];
+        let signer_seeds = &[config_seeds];
+        let slot = Clock::get().unwrap().slot;
+        let forfeited = ctx.accounts.instance_index.play_phase != PlayPhase::Lobby;
+
+        if !forfeited {
+            // Strip the components, then close the now empty Entity
+            let remove_components_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::RemoveComponent {
+                    benefactor: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    entity: ctx.accounts.player.to_account_info(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::req_remove_component(remove_components_ctx, ctx.accounts.player.components.keys().cloned().collect())?;
+
+            let remove_entity_ctx = CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::RemoveEntity {
+                    benefactor: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                    entity: ctx.accounts.player.to_account_info(),
+                    action_bundle: ctx.accounts.config.to_account_info(),
+                    action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                    core_ds: ctx.accounts.coreds.to_account_info(),
+                },
+                signer_seeds
+            );
+            registry::cpi::req_remove_entity(remove_entity_ctx)?;
+
+            ctx.accounts.instance_index.remove_player(player_id, slot);
+            // Give back the room joining made, and the entry fee
+            shrink_instance_index(&ctx.accounts.instance_index.to_account_info(), &ctx.accounts.payer.to_account_info(), PLAYER_INDEX_SIZE)?;
+            let entry_fee = ctx.accounts.instance_index.config.entry_fee;
+            if entry_fee > 0 {
+                pay_from_escrow(&ctx.accounts.escrow, &ctx.accounts.payer.to_account_info(), entry_fee)?;
+                ctx.accounts.escrow.pot -= entry_fee;
+            }
+        } else {
+            let registry_cpi = RegistryCpi {
+                registry_program: ctx.accounts.registry_program.to_account_info(),
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                action_bundle: ctx.accounts.config.to_account_info(),
+                action_bundle_registration: ctx.accounts.ab_registration.to_account_info(),
+                core_ds: ctx.accounts.coreds.to_account_info(),
+                signer_seeds
+            };
+
+            let index = &ctx.accounts.instance_index;
+            let mut seen: Vec<u64> = vec![];
+            let mut accounts = ctx.remaining_accounts.iter();
+            while let Some(unit_info) = accounts.next() {
+                let unit:Account<Entity> = Account::try_from(unit_info)?;
+                if unit.instance != ctx.accounts.registry_instance.instance || !index.units.contains(&unit.entity_id) || seen.contains(&unit.entity_id) {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                seen.push(unit.entity_id);
+
+                let owner_c = unit.components.get(&reference.owner).unwrap();
+                let active_c = unit.components.get(&reference.active).unwrap();
+                let mut active = ComponentActive::try_from_slice(&active_c.data.as_slice()).unwrap();
+                if ComponentOwner::try_from_slice(&owner_c.data.as_slice()).unwrap().player != Some(player_id) || !active.active {
+                    return err!(DominariError::InvalidAccounts)
+                }
+
+                // Tile the Unit is standing on comes right after it
+                let tile_info = match accounts.next() {
+                    Some(tile_info) => tile_info,
+                    None => return err!(DominariError::InvalidAccounts)
+                };
+                let tile:Account<Entity> = Account::try_from(tile_info)?;
+                if tile.instance != ctx.accounts.registry_instance.instance || !index.tiles.contains(&tile.entity_id) {
+                    return err!(DominariError::InvalidAccounts)
+                }
+                let occupant_c = tile.components.get(&reference.occupant).unwrap();
+                let mut occupant = ComponentOccupant::try_from_slice(&occupant_c.data.as_slice()).unwrap();
+                if occupant.occupant_id != Some(unit.entity_id) {
+                    return err!(ComponentErrors::InvalidLocation)
+                }
+
+                occupant.occupant_id = None;
+                registry_cpi.modify_component(tile_info.clone(), vec![(reference.occupant.key(), occupant.try_to_vec().unwrap())])?;
+                active.active = false;
+                registry_cpi.modify_component(unit_info.clone(), vec![(reference.active.key(), active.try_to_vec().unwrap())])?;
+            }
+            // Every living Unit has to be taken off the map
+            if seen.len() != index.get_living_units(player_id) as usize {
+                return err!(DominariError::InvalidAccounts)
+            }
+
+            let index = &mut ctx.accounts.instance_index;
+            index.remove_player(player_id, slot);
+            index.forfeited.push(player_id);
+            if index.play_phase == PlayPhase::Build && index.config.entry_fee > 0 {
+                let entry_fee = index.config.entry_fee;
+                pay_from_escrow(&ctx.accounts.escrow, &ctx.accounts.payer.to_account_info(), entry_fee)?;
+                ctx.accounts.escrow.pot -= entry_fee;
+            }
+        }
+
+        emit!(PlayerLeft {
+            instance: ctx.accounts.registry_instance.instance,
+            player: player_id,
+            forfeited
+        });
+
+        // Last player (or team) left wins
+        let index = &mut ctx.accounts.instance_index;
+        if forfeited && index.one_side_left() {
+            if index.play_phase != PlayPhase::Build {
+                index.winner = index.players.first().cloned();
+            }
+            index.set_play_phase(PlayPhase::Finished, slot);
+            if let Some(winner) = index.winner {
+                emit!(GameWon {
+                    instance: ctx.accounts.registry_instance.instance,
+                    player: winner,
+                    team: index.get_team(winner),
+                    condition: VictoryCondition::LastPlayerStanding
+                });
+            }
+            emit!(GameStateChanged {
+                instance: ctx.accounts.registry_instance.instance,
+                player: player_id,
+                new_state: PlayPhase::Finished
+            });
+        }
+
+        if let Some(new_state) = index.settle_vote(slot) {
+            emit!(GameStateChanged {
+                instance: ctx.accounts.registry_instance.instance,
+                player: player_id,
+                new_state
+            });
+        }
+        settle_escrow(&mut ctx.accounts.escrow, &ctx.accounts.instance_index);
+        Ok(())
+    }
+
+    /**
+     * Pays a player their share of the prize pool once the game is Finished, see PrizeSplit
+     * If the game was abandoned before Play, everyone still in it gets their entry fee back instead
+     * Prizes are saved in the Escrow when the game is Finished, claims just pay them out
+     */
+    pub fn claim_prize(ctx:Context<ClaimPrize>) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        let index = &ctx.accounts.instance_index;
+        if index.play_phase != PlayPhase::Finished {
+            return err!(DominariError::PrizeNotReady)
         }
+
+        let player_stats_c = ctx.accounts.player.components.get(&reference.player_stats).unwrap();
+        let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+        if player_stats.key.key() != ctx.accounts.payer.key() {
+            return err!(ComponentErrors::InvalidOwner)
+        }
+        let player_id = ctx.accounts.player.entity_id;
+        let amount = ctx.accounts.escrow.owed(player_id, index);
+        if amount == 0 {
+            return err!(DominariError::NothingToClaim)
+        }
+
+        pay_from_escrow(&ctx.accounts.escrow, &ctx.accounts.payer.to_account_info(), amount)?;
+        ctx.accounts.escrow.paid += amount;
+        ctx.accounts.escrow.claimed.push(player_id);
+
+        emit!(PrizeClaimed {
+            instance: ctx.accounts.registry_instance.instance,
+            player: player_id,
+            amount,
+            refund: index.abandoned
+        });
         Ok(())
     }
 
-    // Pass in multiple entities through remaining accounts; will iterate and remove them if they are marked inactive
-    // pub fn reclaim_entity_sol(ctx:Context<ReclaimSol>) -> Result<()> {}
-    /*
-    pub fn reclaim_sol(ctx:Context<ReclaimSol>) -> Result<()> {
-        // Can Close *Instance Index* if it's Empty
-        // Can Close Map, Tiles, Features if Leader
-        // Can Close Troops if Troop Owner
+    /**
+     * Permissionless, anyone can prove that a player has met one of the game's Victory Conditions
+     * TileControl needs the Tiles the player owns passed through remaining accounts
+     */
+    pub fn check_victory<'info>(ctx:Context<'_, '_, '_, 'info, CheckVictory<'info>>, condition_idx: u8) -> Result<()> {
+        let reference = &ctx.accounts.config.components;
+        if ctx.accounts.instance_index.play_phase != PlayPhase::Play {
+            return err!(DominariError::GamePaused)
+        }
+
+        if !ctx.accounts.instance_index.players.contains(&ctx.accounts.player.entity_id) {
+            return err!(DominariError::InvalidPlayer)
+        }
+
+        let condition = match ctx.accounts.instance_index.config.victory_conditions.get(condition_idx as usize) {
+            Some(condition) => condition.clone(),
+            None => return err!(DominariError::InvalidVictoryCondition)
+        };
+
+        let slot = Clock::get().unwrap().slot;
+        if !victory_condition_met(&condition, &ctx.accounts.player, ctx.remaining_accounts, &ctx.accounts.instance_index, reference, slot)? {
+            return err!(DominariError::VictoryNotMet)
+        }
+
+        ctx.accounts.instance_index.winner = Some(ctx.accounts.player.entity_id);
+        ctx.accounts.instance_index.set_play_phase(PlayPhase::Finished, slot);
+
+        emit!(GameWon {
+            instance: ctx.accounts.registry_instance.instance,
+            player: ctx.accounts.player.entity_id,
+            team: ctx.accounts.instance_index.get_team(ctx.accounts.player.entity_id),
+            condition
+        });
+        emit!(GameStateChanged {
+            instance: ctx.accounts.registry_instance.instance,
+            player: ctx.accounts.player.entity_id,
+            new_state: PlayPhase::Finished
+        });
+        settle_escrow(&mut ctx.accounts.escrow, &ctx.accounts.instance_index);
         Ok(())
     }
-    */
 }
 
-pub fn get_random_u64(max: u64) -> u64 {
-    let clock = Clock::get().unwrap();
-    let slice = &hash(&clock.slot.to_be_bytes()).to_bytes()[0..8];
-    let num: u64 = u64::from_be_bytes(slice.try_into().unwrap());
-    let target = num/(u64::MAX/max);
-    return target;
-}
\ No newline at end of file
+
+/**
+ * Checks a Victory Condition for a player against the Entities passed in
+ * Entities are validated against the Instance Index, only TileControl needs any
+ */
+pub fn victory_condition_met<'info>(condition: &VictoryCondition, player: &Entity, entities: &[AccountInfo<'info>], index: &InstanceIndex, reference: &RelevantComponentKeys, slot: u64) -> Result<bool> {
+    // Units and Tiles held by teammates count for the player
+    let side = ComponentOwner {
+        owner: None,
+        player: Some(player.entity_id),
+        team: index.get_team(player.entity_id),
+    };
+    match condition {
+        VictoryCondition::Score { threshold } => {
+            let player_stats_c = player.components.get(&reference.player_stats).unwrap();
+            let player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+            return Ok(player_stats.score >= *threshold);
+        },
+        VictoryCondition::LastPlayerStanding => {
+            return Ok(index.last_side_standing(player.entity_id));
+        },
+        VictoryCondition::TileControl { tiles, deadline } => {
+            if slot < *deadline {
+                return Ok(false);
+            }
+
+            let mut owned: Vec<u64> = vec![];
+            for tile_info in entities.iter() {
+                let tile:Account<Entity> = Account::try_from(tile_info)?;
+                if tile.instance != player.instance || !index.tiles.contains(&tile.entity_id) || owned.contains(&tile.entity_id) {
+                    return err!(DominariError::InvalidAccounts)
+                }
+
+                let owner_c = tile.components.get(&reference.owner).unwrap();
+                let tile_owner = ComponentOwner::try_from_slice(&owner_c.data.as_slice()).unwrap();
+                if tile_owner.player.is_none() || !tile_owner.is_friendly(&side) {
+                    return err!(ComponentErrors::InvalidOwner)
+                }
+                owned.push(tile.entity_id);
+            }
+            return Ok(owned.len() >= *tiles as usize);
+        }
+    }
+}
+
+/**
+ * Player that met a Victory Condition going by the Instance Index alone, the best score takes a Score condition
+ * TileControl needs the Tiles passed in, so it's never met here
+ */
+pub fn get_index_victor(condition: &VictoryCondition, index: &InstanceIndex) -> Option<u64> {
+    match condition {
+        VictoryCondition::Score { threshold } => {
+            let mut leader: Option<(u64, u64)> = None;
+            for (player_id, score) in index.get_scores() {
+                if score >= *threshold && leader.map_or(true, |(_, top)| score > top) {
+                    leader = Some((player_id, score));
+                }
+            }
+            leader.map(|(player_id, _)| player_id)
+        },
+        VictoryCondition::LastPlayerStanding => index.players.iter().find(|&&player_id| index.last_side_standing(player_id)).cloned(),
+        VictoryCondition::TileControl { tiles: _, deadline: _ } => None
+    }
+}
+
+/**
+ * Whether the anchor is a Tile or Feature held by the side that's right next to the Tile
+ */
+pub fn is_next_to_territory<'info>(tile: &Entity, anchor_info: Option<&AccountInfo<'info>>, index: &InstanceIndex, reference: &RelevantComponentKeys, side: &ComponentOwner) -> Result<bool> {
+    let anchor:Account<Entity> = match anchor_info {
+        Some(anchor_info) => Account::try_from(anchor_info)?,
+        None => return Ok(false)
+    };
+    if anchor.instance != tile.instance || !(index.tiles.contains(&anchor.entity_id) || index.features.contains(&anchor.entity_id)) {
+        return err!(DominariError::InvalidAccounts)
+    }
+
+    let anchor_owner_c = anchor.components.get(&reference.owner).unwrap();
+    if !ComponentOwner::try_from_slice(&anchor_owner_c.data.as_slice()).unwrap().is_friendly(side) {
+        return Ok(false)
+    }
+
+    // Destroyed Features don't hold any territory
+    if index.features.contains(&anchor.entity_id) {
+        let anchor_active_c = anchor.components.get(&reference.active).unwrap();
+        if !ComponentActive::try_from_slice(&anchor_active_c.data.as_slice()).unwrap().active {
+            return Ok(false)
+        }
+    }
+
+    let anchor_location_c = anchor.components.get(&reference.location).unwrap();
+    let anchor_location = ComponentLocation::try_from_slice(&anchor_location_c.data.as_slice()).unwrap();
+    let tile_location_c = tile.components.get(&reference.location).unwrap();
+    let tile_location = ComponentLocation::try_from_slice(&tile_location_c.data.as_slice()).unwrap();
+    Ok(anchor_location.x.abs_diff(tile_location.x) <= 1 && anchor_location.y.abs_diff(tile_location.y) <= 1)
+}
+
+/**
+ * Player with the best score on the side (team, or just the player) with the highest total score
+ * A tie for the top side is a draw
+ */
+pub fn get_score_leader(index: &InstanceIndex) -> Option<u64> {
+    // Side is (true, team) in team games, (false, player) otherwise
+    let mut side_scores: BTreeMap<(bool, u64), u64> = BTreeMap::new();
+    let mut scores: Vec<(u64, (bool, u64), u64)> = vec![];
+    for (player_id, score) in index.get_scores() {
+        let side = match index.get_team(player_id) {
+            Some(team) => (true, team as u64),
+            None => (false, player_id)
+        };
+        *side_scores.entry(side).or_insert(0) += score;
+        scores.push((player_id, side, score));
+    }
+
+    let top_score = match side_scores.values().max() {
+        Some(top_score) => *top_score,
+        None => return None
+    };
+    let mut top_sides = side_scores.iter().filter(|(_, score)| **score == top_score);
+    let top_side = top_sides.next().unwrap().0.clone();
+    if top_sides.next().is_some() {
+        return None
+    }
+    scores.iter().filter(|(_, side, _)| *side == top_side).max_by_key(|(_, _, score)| *score).map(|(id, _, _)| *id)
+}
+
+/**
+ * Saves every player's prize in the Escrow the moment the game is Finished, before anyone can close their Player
+ * Abandoned games refund entry fees instead, so there's nothing to save
+ */
+pub fn settle_escrow(escrow: &mut InstanceEscrow, index: &InstanceIndex) {
+    if index.play_phase != PlayPhase::Finished || index.abandoned || escrow.payouts.is_some() {
+        return
+    }
+    escrow.payouts = Some(get_payouts(escrow.pot, index));
+}
+
+/**
+ * Every player's share of the pot once the game is Finished, going by the scores in the Instance Index
+ */
+pub fn get_payouts(pot: u64, index: &InstanceIndex) -> Vec<(u64, u64)> {
+    split_pot(pot, &index.config.prize_split, index.winner, &index.get_scores())
+}
+
+/**
+ * Splits the pot between the players in scores, rounding down so it never pays out more than the pot
+ */
+pub fn split_pot(pot: u64, prize_split: &PrizeSplit, winner: Option<u64>, scores: &[(u64, u64)]) -> Vec<(u64, u64)> {
+    let shares: Vec<(u64, u64)> = match prize_split {
+        PrizeSplit::WinnerTakesAll => match winner {
+            Some(winner) => scores.iter().map(|(id, _)| (*id, (*id == winner) as u64)).collect(),
+            None => scores.iter().map(|(id, _)| (*id, 1)).collect()
+        },
+        PrizeSplit::TopThree => {
+            // Winner comes first no matter their score, then everyone else by score
+            let mut places = scores.to_vec();
+            places.sort_by_key(|(id, score)| (Some(*id) != winner, u64::MAX - score, *id));
+            let weights = prize_split.get_place_weights();
+            places.iter().enumerate().map(|(place, (id, _))| (*id, weights.get(place).cloned().unwrap_or(0))).collect()
+        },
+        PrizeSplit::Proportional => {
+            if scores.iter().all(|(_, score)| *score == 0) {
+                scores.iter().map(|(id, _)| (*id, 1)).collect()
+            } else {
+                scores.to_vec()
+            }
+        }
+    };
+
+    let total: u64 = shares.iter().map(|(_, share)| share).sum();
+    shares.iter().map(|(id, share)| {
+        let amount = if total == 0 { 0 } else { (pot as u128 * *share as u128 / total as u128) as u64 };
+        (*id, amount)
+    }).collect()
+}
+
+/**
+ * Moves lamports out of the escrow, it's owned by the program so no CPI is needed
+ * Never pays out more than is left in the pot, or enough to leave the escrow short of rent
+ */
+pub fn pay_from_escrow<'info>(escrow: &Account<'info, InstanceEscrow>, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+    if amount > escrow.pot.saturating_sub(escrow.paid) {
+        return err!(DominariError::EscrowShort)
+    }
+    let escrow_info = escrow.to_account_info();
+    let rent = Rent::get()?.minimum_balance(escrow_info.data_len());
+    if escrow_info.lamports().saturating_sub(amount) < rent {
+        return err!(DominariError::EscrowShort)
+    }
+    **escrow_info.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+/**
+ * Shrinks the Instance Index by the freed bytes and refunds the rent it no longer needs
+ */
+pub fn shrink_instance_index<'info>(index_info: &AccountInfo<'info>, benefactor: &AccountInfo<'info>, freed: usize) -> Result<()> {
+    let new_len = match index_info.data_len().checked_sub(freed) {
+        Some(new_len) => new_len,
+        None => return err!(DominariError::IndexUnderflow)
+    };
+    index_info.realloc(new_len, false)?;
+    let rent_exempt = Rent::get()?.minimum_balance(new_len);
+    let excess = index_info.lamports().saturating_sub(rent_exempt);
+    **index_info.try_borrow_mut_lamports()? -= excess;
+    **benefactor.try_borrow_mut_lamports()? += excess;
+    Ok(())
+}
+
+/**
+ * Tile has Metadata, Location, Feature, Occupant, Owner, Cost and Terrain components
+ * Tiles start out owned by whoever made the map, with no Player
+ */
+
+pub fn get_tile_components(reference: &RelevantComponentKeys, registry_instance: Pubkey, owner: Pubkey, x:u8, y:u8, cost:u64, terrain: Terrain) -> BTreeMap<Pubkey, SerializedComponent> {
+    let mut components: BTreeMap<Pubkey, SerializedComponent> = BTreeMap::new();
+    let metadata = ComponentMetadata {
+        name: format!("Tile ({x}, {y})"),
+        entity_type: EntityType::Tile,
+        registry_instance,
+    }.try_to_vec().unwrap();
+    components.insert(reference.metadata.key(), SerializedComponent { 
+        max_size: ComponentMetadata::get_max_size(),
+        data: metadata
+    });
+
+    let location = ComponentLocation {
+        x,
+        y,
+    }.try_to_vec().unwrap();
+    components.insert(reference.location.key(), SerializedComponent { 
+        max_size: ComponentLocation::get_max_size(),
+        data: location
+    });
+
+    let feature = ComponentFeature {
+        feature_id: None,
+    }.try_to_vec().unwrap();
+    components.insert(reference.feature.key(), SerializedComponent { 
+        max_size: ComponentFeature::get_max_size(),
+        data: feature
+    });
+
+    let occupant = ComponentOccupant {
+        occupant_id: None,
+    }.try_to_vec().unwrap();
+    components.insert(reference.occupant.key(), SerializedComponent { 
+        max_size: ComponentOccupant::get_max_size(),
+        data: occupant
+    });
+
+    let owner = ComponentOwner {
+        owner: Some(owner),
+        player: None,
+        team: None,
+    }.try_to_vec().unwrap();
+    components.insert(reference.owner.key(), SerializedComponent { 
+        max_size: ComponentOwner::get_max_size(),
+        data: owner
+    });
+
+    let cost_component = ComponentCost {
+        lamports: cost,
+    }.try_to_vec().unwrap();
+    components.insert(reference.cost.key(), SerializedComponent { 
+        max_size: ComponentCost::get_max_size(),
+        data: cost_component
+    });
+
+    let terrain_component = ComponentTerrain {
+        terrain,
+    }.try_to_vec().unwrap();
+    components.insert(reference.terrain.key(), SerializedComponent { 
+        max_size: ComponentTerrain::get_max_size(),
+        data: terrain_component
+    });
+    return components;
+}
+
+/**
+ * Cost of moving between two adjacent tiles under the instance's Distance Metric
+ * Scaled by the Terrain being moved onto
+ */
+pub fn get_step_cost(metric: &DistanceMetric, from: &ComponentLocation, to: &ComponentLocation, terrain: &Terrain, class: &TroopClass) -> Result<u64> {
+    let step = match metric.step_cost(from.x.abs_diff(to.x), from.y.abs_diff(to.y)) {
+        Some(step) => step,
+        None => return err!(ComponentErrors::InvalidPath)
+    };
+    match terrain.movement_cost(class) {
+        Some(terrain_cost) => Ok(step * terrain_cost / 100),
+        None => err!(ComponentErrors::ImpassableTerrain)
+    }
+}
+
+/**
+ * Tiles made before Terrain existed are treated as Plains
+ */
+pub fn get_terrain(tile: &Entity, reference: &RelevantComponentKeys) -> Terrain {
+    match tile.components.get(&reference.terrain) {
+        Some(terrain_c) => ComponentTerrain::try_from_slice(&terrain_c.data.as_slice()).unwrap().terrain,
+        None => Terrain::Plains
+    }
+}
+
+/**
+ * Checks the optional LastUsed and Uses components on a Feature
+ * Returns the updated components to write back to the Feature if it can be used
+ */
+pub fn charge_feature_use(feature: &Entity, reference: &RelevantComponentKeys, slot: u64) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let mut updates: Vec<(Pubkey, Vec<u8>)> = vec![];
+
+    let last_used_c = feature.components.get(&reference.last_used);
+    if last_used_c.is_some() {
+        let mut last_used = ComponentLastUsed::try_from_slice(&last_used_c.unwrap().data.as_slice()).unwrap();
+        if last_used.last_used != 0 && (last_used.last_used + last_used.recovery) >= slot {
+            return err!(ComponentErrors::FeatureRecovering)
+        }
+        last_used.last_used = slot;
+        updates.push((reference.last_used.key(), last_used.try_to_vec().unwrap()));
+    }
+
+    let uses_c = feature.components.get(&reference.uses);
+    if uses_c.is_some() {
+        let mut uses = ComponentUses::try_from_slice(&uses_c.unwrap().data.as_slice()).unwrap();
+        if uses.uses_left == 0 {
+            return err!(ComponentErrors::FeatureExhausted)
+        }
+        uses.uses_left -= 1;
+        updates.push((reference.uses.key(), uses.try_to_vec().unwrap()));
+    }
+
+    return Ok(updates);
+}
+
+/**
+ * Rolls the attacker's damage against the defender, applying the class bonuses
+ */
+pub fn roll_damage(oracle: &mut dyn RandomnessOracle, attacker_damage: &ComponentDamage, defender: &Entity, defending_tile: &Entity, reference: &RelevantComponentKeys) -> u64 {
+    let mut dmg = oracle.get_random_u64(attacker_damage.max_damage); 
+        
+    // check if defender is Feature, if not, look for it's TroopClass
+    let defender_metadata_c = defender.components.get(&reference.metadata).unwrap();
+    let defender_metadata = ComponentMetadata::try_from_slice(&defender_metadata_c.data.as_slice()).unwrap();
+
+    let mut in_cover = true;
+    if defender_metadata.entity_type == EntityType::Feature {
+        dmg += attacker_damage.bonus_feature as u64;
+    } else {
+        let defender_troop_class_c = defender.components.get(&reference.troop_class).unwrap();
+        let defender_troop_class = ComponentTroopClass::try_from_slice(&defender_troop_class_c.data.as_slice()).unwrap();
+        match defender_troop_class.class {
+            TroopClass::Aircraft => {
+                dmg += attacker_damage.bonus_aircraft as u64;
+                in_cover = false;
+            },
+            TroopClass::Infantry => dmg += attacker_damage.bonus_infantry as u64,
+            TroopClass::Armor => dmg += attacker_damage.bonus_armor as u64,
+        }
+    }
+
+    if dmg < attacker_damage.min_damage {
+        dmg = attacker_damage.min_damage;
+    }
+
+    // Aircraft don't get cover from the terrain below them
+    if in_cover {
+        dmg -= dmg * get_terrain(defending_tile, reference).defense_bonus() / 100;
+    }
+    return dmg;
+}
+
+/**
+ * Checks the Tile is the one the entity is on, as its Occupant or its Feature
+ * Rolls take cover from the Tile, so it has to be checked before rolling
+ */
+pub fn check_on_tile(entity: &Entity, tile: &Entity, reference: &RelevantComponentKeys) -> Result<()> {
+    let entity_location = ComponentLocation::try_from_slice(&entity.components.get(&reference.location).unwrap().data.as_slice()).unwrap();
+    let tile_location = ComponentLocation::try_from_slice(&tile.components.get(&reference.location).unwrap().data.as_slice()).unwrap();
+    if tile_location.x != entity_location.x || tile_location.y != entity_location.y {
+        return err!(ComponentErrors::InvalidLocation)
+    }
+
+    let entity_metadata = ComponentMetadata::try_from_slice(&entity.components.get(&reference.metadata).unwrap().data.as_slice()).unwrap();
+    let on_tile = if entity_metadata.entity_type == EntityType::Feature {
+        ComponentFeature::try_from_slice(&tile.components.get(&reference.feature).unwrap().data.as_slice()).unwrap().feature_id
+    } else {
+        ComponentOccupant::try_from_slice(&tile.components.get(&reference.occupant).unwrap().data.as_slice()).unwrap().occupant_id
+    };
+    if on_tile != Some(entity.entity_id) {
+        return err!(ComponentErrors::InvalidLocation)
+    }
+    Ok(())
+}
+
+/**
+ * Applies damage to the defender's Health
+ * If the defender dies, it's marked inactive and removed from the defending tile
+ * The defending tile has to be checked with check_on_tile first
+ * Returns true if the defender was killed
+ */
+pub fn apply_damage<'info>(registry_cpi: &RegistryCpi<'_, 'info>, reference: &RelevantComponentKeys, defender: &Account<'info, Entity>, defending_tile: &Account<'info, Entity>, dmg: u64) -> Result<bool> {
+    let defender_active_c = defender.components.get(&reference.active).unwrap();
+    let mut defender_active = ComponentActive::try_from_slice(&defender_active_c.data.as_slice()).unwrap();
+    let defender_health_c = defender.components.get(&reference.health);
+    if defender_health_c.is_none() {
+        return err!(ComponentErrors::NoHealthComponent)
+    }
+    let mut defender_health = ComponentHealth::try_from_slice(&defender_health_c.unwrap().data.as_slice()).unwrap();
+    let defender_metadata_c = defender.components.get(&reference.metadata).unwrap();
+    let defender_metadata = ComponentMetadata::try_from_slice(&defender_metadata_c.data.as_slice()).unwrap();
+
+    let killed = dmg >= defender_health.health;
+    if killed {
+        defender_health.health = 0;
+        defender_active.active = false;
+
+        // Modify the defending tile to remove the defender
+        if defender_metadata.entity_type == EntityType::Feature {
+            let tile_feature_c = defending_tile.components.get(&reference.feature).unwrap();
+            let mut tile_feature = ComponentFeature::try_from_slice(&tile_feature_c.data.as_slice()).unwrap();
+            tile_feature.feature_id = None;
+            registry_cpi.modify_component(defending_tile.to_account_info(), vec![(reference.feature.key(), tile_feature.try_to_vec().unwrap())])?;
+        } else {
+            let tile_occupant_c = defending_tile.components.get(&reference.occupant).unwrap();
+            let mut tile_occupant = ComponentOccupant::try_from_slice(&tile_occupant_c.data.as_slice()).unwrap();
+            tile_occupant.occupant_id = None;
+            registry_cpi.modify_component(defending_tile.to_account_info(), vec![(reference.occupant.key(), tile_occupant.try_to_vec().unwrap())])?;
+        }
+    } else {
+        defender_health.health -= dmg;
+    }
+
+    registry_cpi.modify_component(defender.to_account_info(), vec![
+        (reference.health.key(), defender_health.try_to_vec().unwrap()),
+        (reference.active.key(), defender_active.try_to_vec().unwrap()),
+    ])?;
+    return Ok(killed);
+}
+
+/**
+ * Adds the kill and the dead entity's Value to the player's stats, and their score in the Instance Index
+ * Returns the Value credited
+ */
+pub fn credit_kill<'info>(registry_cpi: &RegistryCpi<'_, 'info>, reference: &RelevantComponentKeys, index: &mut InstanceIndex, player: &Account<'info, Entity>, killed: &Entity) -> Result<u64> {
+    let value = match killed.components.get(&reference.value) {
+        Some(value_c) => ComponentValue::try_from_slice(&value_c.data.as_slice()).unwrap().value,
+        None => 0
+    };
+    let player_stats_c = player.components.get(&reference.player_stats).unwrap();
+    let mut player_stats = ComponentPlayerStats::try_from_slice(&player_stats_c.data.as_slice()).unwrap();
+    player_stats.kills += 1;
+    player_stats.score += value;
+    registry_cpi.modify_component(player.to_account_info(), vec![(reference.player_stats.key(), player_stats.try_to_vec().unwrap())])?;
+    index.add_score(player.entity_id, value);
+    return Ok(value);
+}
+
+/**
+ * Takes a dead Unit off its player's living Unit count in the Instance Index
+ */
+pub fn record_death(index: &mut InstanceIndex, reference: &RelevantComponentKeys, killed: &Entity) {
+    let metadata_c = killed.components.get(&reference.metadata).unwrap();
+    if ComponentMetadata::try_from_slice(&metadata_c.data.as_slice()).unwrap().entity_type != EntityType::Unit {
+        return;
+    }
+    let owner_c = killed.components.get(&reference.owner).unwrap();
+    if let Some(player) = ComponentOwner::try_from_slice(&owner_c.data.as_slice()).unwrap().player {
+        index.remove_living_unit(player);
+    }
+}
+
+/**
+ * Transfers the Blueprint's Cost (if it has one) from the payer to the instance Treasury
+ * Returns the lamports paid
+ */
+pub fn pay_blueprint_cost<'info>(blueprint: &Blueprint, reference: &RelevantComponentKeys, payer: AccountInfo<'info>, treasury: AccountInfo<'info>, system_program: AccountInfo<'info>) -> Result<u64> {
+    let cost_c = blueprint.components.get(&reference.cost);
+    if cost_c.is_none() {
+        return Ok(0);
+    }
+    let cost = ComponentCost::try_from_slice(&cost_c.unwrap().data.as_slice()).unwrap();
+    if cost.lamports == 0 {
+        return Ok(0);
+    }
+
+    let transfer_ctx = CpiContext::new(
+        system_program,
+        anchor_lang::system_program::Transfer {
+            from: payer,
+            to: treasury,
+        }
+    );
+    anchor_lang::system_program::transfer(transfer_ctx, cost.lamports)?;
+    return Ok(cost.lamports);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(payouts: &Vec<(u64, u64)>, player: u64) -> u64 {
+        payouts.iter().find(|(id, _)| *id == player).unwrap().1
+    }
+
+    #[test]
+    fn split_pot_winner_takes_all() {
+        let scores = vec![(1, 5), (2, 50), (3, 0)];
+        let payouts = split_pot(300, &PrizeSplit::WinnerTakesAll, Some(1), &scores);
+        assert_eq!(payouts, vec![(1, 300), (2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn split_pot_no_winner_splits_evenly() {
+        let scores = vec![(1, 5), (2, 50), (3, 0)];
+        let payouts = split_pot(300, &PrizeSplit::WinnerTakesAll, None, &scores);
+        assert_eq!(payouts, vec![(1, 100), (2, 100), (3, 100)]);
+    }
+
+    #[test]
+    fn split_pot_top_three_puts_the_winner_first() {
+        let scores = vec![(1, 10), (2, 40), (3, 30), (4, 20)];
+        let payouts = split_pot(1000, &PrizeSplit::TopThree, Some(1), &scores);
+        assert_eq!(amount(&payouts, 1), 500);
+        assert_eq!(amount(&payouts, 2), 300);
+        assert_eq!(amount(&payouts, 3), 200);
+        assert_eq!(amount(&payouts, 4), 0);
+    }
+
+    #[test]
+    fn split_pot_top_three_with_two_players_reweights() {
+        let scores = vec![(1, 10), (2, 40)];
+        let payouts = split_pot(800, &PrizeSplit::TopThree, None, &scores);
+        assert_eq!(amount(&payouts, 2), 500);
+        assert_eq!(amount(&payouts, 1), 300);
+    }
+
+    #[test]
+    fn split_pot_proportional_goes_by_score() {
+        let scores = vec![(1, 10), (2, 30), (3, 0)];
+        let payouts = split_pot(400, &PrizeSplit::Proportional, Some(2), &scores);
+        assert_eq!(payouts, vec![(1, 100), (2, 300), (3, 0)]);
+    }
+
+    #[test]
+    fn split_pot_proportional_without_scores_splits_evenly() {
+        let scores = vec![(1, 0), (2, 0)];
+        let payouts = split_pot(401, &PrizeSplit::Proportional, None, &scores);
+        assert_eq!(payouts, vec![(1, 200), (2, 200)]);
+    }
+
+    #[test]
+    fn split_pot_never_pays_out_more_than_the_pot() {
+        let scores = vec![(1, 1), (2, 1), (3, 1)];
+        for split in [PrizeSplit::WinnerTakesAll, PrizeSplit::TopThree, PrizeSplit::Proportional] {
+            let payouts = split_pot(100, &split, None, &scores);
+            assert!(payouts.iter().map(|(_, amount)| amount).sum::<u64>() <= 100);
+        }
+    }
+
+    fn scored_index(scores: &[(u64, u64)]) -> InstanceIndex {
+        InstanceIndex {
+            config: GameConfig { starting_cards: vec![Pubkey::default()], ..Default::default() },
+            players: scores.iter().map(|(id, _)| *id).collect(),
+            player_scores: scores.iter().cloned().collect(),
+            next_entity_id: FIRST_ENTITY_ID,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_index_victor_score_goes_to_the_best_score_over_the_threshold() {
+        let index = scored_index(&[(1, 40), (2, 60), (3, 60)]);
+        assert_eq!(get_index_victor(&VictoryCondition::Score { threshold: 50 }, &index), Some(2));
+        assert_eq!(get_index_victor(&VictoryCondition::Score { threshold: 70 }, &index), None);
+    }
+
+    #[test]
+    fn get_index_victor_last_player_standing() {
+        let mut index = scored_index(&[(1, 0), (2, 0)]);
+        index.player_units = BTreeMap::from([(1, 2), (2, 0)]);
+        index.deployed = vec![1];
+        assert_eq!(get_index_victor(&VictoryCondition::LastPlayerStanding, &index), None);
+        index.deployed = vec![1, 2];
+        assert_eq!(get_index_victor(&VictoryCondition::LastPlayerStanding, &index), Some(1));
+    }
+
+    #[test]
+    fn get_index_victor_leaves_tile_control_to_the_claimant() {
+        let index = scored_index(&[(1, 100)]);
+        assert_eq!(get_index_victor(&VictoryCondition::TileControl { tiles: 1, deadline: 0 }, &index), None);
+    }
+
+    #[test]
+    fn settle_escrow_saves_prizes_once_finished() {
+        let mut index = scored_index(&[(1, 10), (2, 30)]);
+        index.config.prize_split = PrizeSplit::WinnerTakesAll;
+        index.winner = Some(2);
+        let mut escrow = InstanceEscrow { instance: 0, pot: 100, paid: 0, claimed: vec![], payouts: None };
+
+        index.play_phase = PlayPhase::Play;
+        settle_escrow(&mut escrow, &index);
+        assert_eq!(escrow.payouts, None);
+
+        index.play_phase = PlayPhase::Finished;
+        settle_escrow(&mut escrow, &index);
+        assert_eq!(escrow.payouts, Some(vec![(1, 0), (2, 100)]));
+
+        // Closing a Player afterwards doesn't change what's been saved
+        index.remove_player(2, 0);
+        settle_escrow(&mut escrow, &index);
+        assert_eq!(escrow.payouts, Some(vec![(1, 0), (2, 100)]));
+    }
+
+    #[test]
+    fn settle_escrow_leaves_abandoned_games_to_refunds() {
+        let mut index = scored_index(&[(1, 0), (2, 0)]);
+        index.play_phase = PlayPhase::Finished;
+        index.abandoned = true;
+        let mut escrow = InstanceEscrow { instance: 0, pot: 100, paid: 0, claimed: vec![], payouts: None };
+        settle_escrow(&mut escrow, &index);
+        assert_eq!(escrow.payouts, None);
+    }
+}
@@ -0,0 +1,251 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::*;
+
+use crate::account::RandomnessCommitment;
+use crate::error::DominariError;
+
+// SlotHashes sysvar is a u64 length followed by (slot: u64, hash: [u8;32]) entries, newest first
+const SLOT_HASHES_LEN_SIZE: usize = 8;
+const SLOT_HASH_ENTRY_SIZE: usize = 8 + 32;
+
+/**
+ * Anything that can produce rolls for the game
+ * Lets a local test oracle stand in for commit-reveal during tests
+ */
+pub trait RandomnessOracle {
+    /**
+     * Returns a number in [0, max), or 0 if max is 0
+     */
+    fn get_random_u64(&mut self, max: u64) -> u64;
+}
+
+/**
+ * Rolls by hashing a seed with a counter, so one seed covers every roll in an instruction
+ * Commit-reveal and the local test oracle only differ in how the seed is made
+ */
+pub struct SeededOracle {
+    seed: [u8; 32],
+    nonce: u64,
+}
+
+impl SeededOracle {
+    /**
+     * Mixes a player's revealed secret with the hash of the first slot after the one they committed in, and the newest slot hash when it's revealed
+     * Nobody knows the first hash when the commitment is made, and the secret keeps the validator from knowing the outcome
+     * The first hash is public before the reveal is sent, so the newest one keeps the player from working out the roll and only revealing good ones
+     */
+    pub fn commit_reveal(reveal: &[u8; 32], slot_hash: &[u8; 32], reveal_slot_hash: &[u8; 32]) -> Self {
+        SeededOracle {
+            seed: hashv(&[reveal.as_ref(), slot_hash.as_ref(), reveal_slot_hash.as_ref()]).to_bytes(),
+            nonce: 0,
+        }
+    }
+
+    /**
+     * Deterministic oracle seeded only by the reveal
+     * Only used when the program is built with the `test-oracle` feature
+     */
+    pub fn local_test(reveal: &[u8; 32]) -> Self {
+        SeededOracle { seed: *reveal, nonce: 0 }
+    }
+}
+
+impl RandomnessOracle for SeededOracle {
+    fn get_random_u64(&mut self, max: u64) -> u64 {
+        if max == 0 {
+            return 0;
+        }
+        let roll = hashv(&[self.seed.as_ref(), self.nonce.to_be_bytes().as_ref()]).to_bytes();
+        self.nonce += 1;
+        let num = u64::from_be_bytes(roll[0..8].try_into().unwrap());
+        return num % max;
+    }
+}
+
+/**
+ * Checks the reveal against the player's commitment and consumes it
+ * actor and target are the entities the instruction rolls for, they have to be the ones committed to
+ * Returns the oracle rolls for this instruction should be made with
+ */
+#[cfg(not(feature = "test-oracle"))]
+pub fn load_oracle(commitment: &mut RandomnessCommitment, slot_hashes_info: &AccountInfo, reveal: [u8; 32], actor: u64, target: u64) -> Result<Box<dyn RandomnessOracle>> {
+    if slot_hashes_info.key() != anchor_lang::solana_program::sysvar::slot_hashes::ID {
+        return err!(DominariError::InvalidAccounts)
+    }
+    if commitment.commitment == [0; 32] {
+        return err!(DominariError::NoCommitment)
+    }
+    if hash(&reveal).to_bytes() != commitment.commitment {
+        return err!(DominariError::InvalidReveal)
+    }
+    if commitment.actor != actor || commitment.target != target {
+        return err!(DominariError::CommitmentMismatch)
+    }
+
+    let slot_hashes = slot_hashes_info.try_borrow_data()?;
+    let (next_slot, slot_hash) = get_next_slot_hash(&slot_hashes, commitment.slot)?;
+    // Newest hash has to come after the one the commitment is tied to, or it adds nothing the player didn't already know
+    let (latest_slot, reveal_slot_hash) = get_latest_slot_hash(&slot_hashes)?;
+    if latest_slot <= next_slot {
+        return err!(DominariError::CommitmentPending)
+    }
+
+    // Commitments can only be used for one instruction
+    commitment.commitment = [0; 32];
+    return Ok(Box::new(SeededOracle::commit_reveal(&reveal, &slot_hash, &reveal_slot_hash)));
+}
+
+#[cfg(feature = "test-oracle")]
+pub fn load_oracle(_commitment: &mut RandomnessCommitment, _slot_hashes_info: &AccountInfo, reveal: [u8; 32], _actor: u64, _target: u64) -> Result<Box<dyn RandomnessOracle>> {
+    return Ok(Box::new(SeededOracle::local_test(&reveal)));
+}
+
+/**
+ * Binary searches the raw SlotHashes sysvar data for the first slot after the given one, and its hash
+ * Deserializing the whole sysvar blows the compute budget
+ */
+pub fn get_next_slot_hash(data: &[u8], slot: u64) -> Result<(u64, [u8; 32])> {
+    let len = u64::from_le_bytes(data[0..SLOT_HASHES_LEN_SIZE].try_into().unwrap()) as usize;
+
+    // Entries are newest first, find the first one at or before the slot
+    let mut low = 0;
+    let mut high = len;
+    while low < high {
+        let mid = (low + high) / 2;
+        let offset = SLOT_HASHES_LEN_SIZE + (mid * SLOT_HASH_ENTRY_SIZE);
+        let entry_slot = u64::from_le_bytes(data[offset..offset+8].try_into().unwrap());
+        if entry_slot > slot {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+
+    // Nothing after the slot yet, or the slot fell out of the sysvar so we can't tell which one came next
+    if low == 0 {
+        return err!(DominariError::CommitmentPending)
+    }
+    if low == len {
+        return err!(DominariError::CommitmentExpired)
+    }
+    return Ok(get_slot_hash_entry(data, low - 1));
+}
+
+/**
+ * Newest slot in the raw SlotHashes sysvar data and its hash, the slot right before the current one
+ */
+pub fn get_latest_slot_hash(data: &[u8]) -> Result<(u64, [u8; 32])> {
+    let len = u64::from_le_bytes(data[0..SLOT_HASHES_LEN_SIZE].try_into().unwrap()) as usize;
+    if len == 0 {
+        return err!(DominariError::CommitmentPending)
+    }
+    return Ok(get_slot_hash_entry(data, 0));
+}
+
+fn get_slot_hash_entry(data: &[u8], idx: usize) -> (u64, [u8; 32]) {
+    let offset = SLOT_HASHES_LEN_SIZE + (idx * SLOT_HASH_ENTRY_SIZE);
+    let slot = u64::from_le_bytes(data[offset..offset+8].try_into().unwrap());
+    return (slot, data[offset+8..offset+SLOT_HASH_ENTRY_SIZE].try_into().unwrap());
+}
+
+/**
+ * Same as load_oracle, for instructions that take the commitment through remaining accounts
+ * Writes the consumed commitment back since Anchor won't do it for us
+ */
+pub fn load_oracle_from_accounts<'info>(commitment_info: &AccountInfo<'info>, slot_hashes_info: &AccountInfo<'info>, payer: Pubkey, instance: u64, reveal: Option<[u8; 32]>, actor: u64, target: u64) -> Result<Box<dyn RandomnessOracle>> {
+    if reveal.is_none() {
+        return err!(DominariError::NoCommitment)
+    }
+    let mut commitment: Account<RandomnessCommitment> = Account::try_from(commitment_info)?;
+    if commitment.player != payer || commitment.instance != instance {
+        return err!(DominariError::InvalidAccounts)
+    }
+    let oracle = load_oracle(&mut commitment, slot_hashes_info, reveal.unwrap(), actor, target)?;
+    commitment.exit(&crate::id())?;
+    return Ok(oracle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Raw SlotHashes data, entries given newest first like the sysvar
+    fn slot_hashes(entries: &[u64]) -> Vec<u8> {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for slot in entries {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[*slot as u8; 32]);
+        }
+        data
+    }
+
+    fn error_name<T>(result: Result<T>) -> String {
+        match result {
+            Err(anchor_lang::error::Error::AnchorError(e)) => e.error_name,
+            _ => String::new()
+        }
+    }
+
+    fn rolls(oracle: &mut SeededOracle) -> Vec<u64> {
+        (0..4).map(|_| oracle.get_random_u64(u64::MAX)).collect()
+    }
+
+    #[test]
+    fn seeded_oracle_same_seed_same_rolls() {
+        let mut a = SeededOracle::local_test(&[7; 32]);
+        let mut b = SeededOracle::local_test(&[7; 32]);
+        assert_eq!(rolls(&mut a), rolls(&mut b));
+
+        let mut a = SeededOracle::commit_reveal(&[7; 32], &[1; 32], &[2; 32]);
+        let mut b = SeededOracle::commit_reveal(&[7; 32], &[1; 32], &[2; 32]);
+        assert_eq!(rolls(&mut a), rolls(&mut b));
+    }
+
+    #[test]
+    fn seeded_oracle_each_roll_moves_on() {
+        let mut oracle = SeededOracle::local_test(&[7; 32]);
+        let first = rolls(&mut oracle);
+        assert_ne!(first[0], first[1]);
+        assert_ne!(first, rolls(&mut oracle));
+    }
+
+    #[test]
+    fn commit_reveal_depends_on_the_reveal_slot_hash() {
+        let mut a = SeededOracle::commit_reveal(&[7; 32], &[1; 32], &[2; 32]);
+        let mut b = SeededOracle::commit_reveal(&[7; 32], &[1; 32], &[3; 32]);
+        assert_ne!(rolls(&mut a), rolls(&mut b));
+    }
+
+    #[test]
+    fn get_random_u64_stays_under_max() {
+        let mut oracle = SeededOracle::local_test(&[7; 32]);
+        assert_eq!(oracle.get_random_u64(0), 0);
+        assert_eq!(oracle.get_random_u64(1), 0);
+        for _ in 0..32 {
+            assert!(oracle.get_random_u64(6) < 6);
+        }
+    }
+
+    #[test]
+    fn get_next_slot_hash_finds_the_first_slot_after() {
+        let data = slot_hashes(&[105, 103, 100, 99]);
+        assert_eq!(get_next_slot_hash(&data, 100).unwrap(), (103, [103; 32]));
+        assert_eq!(get_next_slot_hash(&data, 101).unwrap(), (103, [103; 32]));
+        assert_eq!(get_next_slot_hash(&data, 103).unwrap(), (105, [105; 32]));
+        assert_eq!(get_next_slot_hash(&data, 99).unwrap(), (100, [100; 32]));
+    }
+
+    #[test]
+    fn get_next_slot_hash_pending_and_expired() {
+        let data = slot_hashes(&[105, 103, 100, 99]);
+        assert_eq!(error_name(get_next_slot_hash(&data, 105)), "CommitmentPending");
+        assert_eq!(error_name(get_next_slot_hash(&data, 110)), "CommitmentPending");
+        assert_eq!(error_name(get_next_slot_hash(&data, 98)), "CommitmentExpired");
+    }
+
+    #[test]
+    fn get_latest_slot_hash_is_the_newest_entry() {
+        assert_eq!(get_latest_slot_hash(&slot_hashes(&[105, 103, 100])).unwrap(), (105, [105; 32]));
+        assert_eq!(error_name(get_latest_slot_hash(&slot_hashes(&[]))), "CommitmentPending");
+    }
+}
@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
 
 use core_ds::account::MaxSize;
+use crate::constant::MOVEMENT_SCALE;
+use crate::account::PlayPhase;
 
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone)]
@@ -24,12 +26,15 @@ pub struct RelevantComponentKeys {
     pub troop_class: Pubkey,
     pub active: Pubkey,
     pub cost: Pubkey,
-    pub offchain_metadata: Pubkey
+    pub offchain_metadata: Pubkey,
+    pub terrain: Pubkey,
+    pub sight: Pubkey,
+    pub portal: Pubkey,
 }
 
 impl MaxSize for RelevantComponentKeys {
     fn get_max_size() -> u64 {
-        return 32*20;
+        return 32*23;
     }
 }
 
@@ -39,15 +44,98 @@ impl MaxSize for RelevantComponentKeys {
  * that's Vec<string> that we then map into Vec<Pubkey> for this GameConfig
  */
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
-#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone)]
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, Default)]
 pub struct GameConfig {
     pub max_players: u16,
     pub starting_cards: Vec<Pubkey>,
+    pub victory_conditions: Vec<VictoryCondition>,
+    // Some(slots) makes the game turn based, a turn that runs past the timeout can be ended by anyone
+    pub turn_timeout: Option<u64>,
+    // Only prices movement, attack and feature ranges are always straight line distance
+    pub distance_metric: DistanceMetric,
+    // Number of teams players join, 0 is free for all
+    pub teams: u8,
+    // Some(slots) each phase can last, once it runs out anyone can crank the game into the next phase
+    pub lobby_duration: Option<u64>,
+    pub build_duration: Option<u64>,
+    pub play_duration: Option<u64>,
+    // Lamports each player pays into the prize pool when they join, 0 for free games
+    pub entry_fee: u64,
+    pub prize_split: PrizeSplit,
+    // Surviving defenders strike back at attackers in their own range
+    pub counter_attacks: bool,
+}
+
+impl GameConfig {
+    /**
+     * Only Lobby, Build and Play run on the clock
+     */
+    pub fn get_phase_duration(&self, phase: &PlayPhase) -> Option<u64> {
+        match phase {
+            PlayPhase::Lobby => self.lobby_duration,
+            PlayPhase::Build => self.build_duration,
+            PlayPhase::Play => self.play_duration,
+            _ => None
+        }
+    }
 }
 
 impl DependentMaxSize for GameConfig {
     fn get_max_size(&self) -> u64 {
-        return 2 + 4 + (self.starting_cards.len() as u64 * 32_u64);
+        return 2 + 4 + (self.starting_cards.len() as u64 * 32_u64) + 4 + (self.victory_conditions.len() as u64 * VictoryCondition::get_max_size()) + 9 + 1 + 1 + 9 + 9 + 9 + 8 + 1 + 1;
+    }
+}
+
+/**
+ * How the prize pool is split up once the game is Finished
+ */
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, PartialEq)]
+pub enum PrizeSplit {
+    // Winner gets the whole pool, a game without a winner splits it evenly
+    WinnerTakesAll,
+    // 50/30/20 between the winner and the next best scores
+    TopThree,
+    // Everyone gets their share of the total score
+    Proportional,
+}
+
+impl Default for PrizeSplit {
+    fn default() -> Self {
+        PrizeSplit::WinnerTakesAll
+    }
+}
+
+impl PrizeSplit {
+    /**
+     * Share of the pool for each place, only TopThree cares about places
+     */
+    pub fn get_place_weights(&self) -> Vec<u64> {
+        match self {
+            PrizeSplit::TopThree => vec![50, 30, 20],
+            _ => vec![]
+        }
+    }
+}
+
+/**
+ * Any one of the conditions being met ends the game
+ * Proven by calling check_victory with the winning player, in team games their whole team wins
+ */
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, PartialEq)]
+pub enum VictoryCondition {
+    // First player to reach the score
+    Score { threshold: u64 },
+    // Only player (or team) left that still has living units or cards it never spawned
+    LastPlayerStanding,
+    // Player (or team) controls at least `tiles` tiles once the deadline slot has passed
+    TileControl { tiles: u16, deadline: u64 },
+}
+
+impl MaxSize for VictoryCondition {
+    fn get_max_size() -> u64 {
+        return 1 + 2 + 8;
     }
 }
 
@@ -55,6 +143,40 @@ pub trait DependentMaxSize {
     fn get_max_size(&self) -> u64;
 }
 
+/**
+ * How far a single step between adjacent tiles costs
+ * Chebyshev lets diagonals cost the same as straight steps, Manhattan doesn't allow diagonal steps,
+ * Euclidean charges ~1.41 for diagonals
+ * Movement only, attack ranges don't go by the metric
+ */
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone, PartialEq)]
+pub enum DistanceMetric {
+    Chebyshev,
+    Manhattan,
+    Euclidean,
+}
+
+impl Default for DistanceMetric {
+    fn default() -> Self {
+        DistanceMetric::Chebyshev
+    }
+}
+
+impl DistanceMetric {
+    /**
+     * Cost of a step scaled by MOVEMENT_SCALE, None if the tiles aren't adjacent
+     */
+    pub fn step_cost(&self, dx: u8, dy: u8) -> Option<u64> {
+        match (self, dx, dy) {
+            (_, 0, 1) | (_, 1, 0) => Some(MOVEMENT_SCALE),
+            (DistanceMetric::Chebyshev, 1, 1) => Some(MOVEMENT_SCALE),
+            (DistanceMetric::Euclidean, 1, 1) => Some(MOVEMENT_SCALE * 141 / 100),
+            _ => None
+        }
+    }
+}
+
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[derive(AnchorDeserialize, AnchorSerialize, Debug, Clone)]
 pub enum UseFeatureType {
@@ -62,4 +184,40 @@ pub enum UseFeatureType {
     Portal,
     Attack,
     Loot,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_cost_straight_steps_cost_the_same_everywhere() {
+        for metric in [DistanceMetric::Chebyshev, DistanceMetric::Manhattan, DistanceMetric::Euclidean] {
+            assert_eq!(metric.step_cost(1, 0), Some(MOVEMENT_SCALE));
+            assert_eq!(metric.step_cost(0, 1), Some(MOVEMENT_SCALE));
+        }
+    }
+
+    #[test]
+    fn step_cost_diagonals_depend_on_the_metric() {
+        assert_eq!(DistanceMetric::Chebyshev.step_cost(1, 1), Some(MOVEMENT_SCALE));
+        assert_eq!(DistanceMetric::Manhattan.step_cost(1, 1), None);
+        assert_eq!(DistanceMetric::Euclidean.step_cost(1, 1), Some(141));
+    }
+
+    #[test]
+    fn step_cost_rejects_tiles_that_arent_adjacent() {
+        for metric in [DistanceMetric::Chebyshev, DistanceMetric::Manhattan, DistanceMetric::Euclidean] {
+            assert_eq!(metric.step_cost(0, 0), None);
+            assert_eq!(metric.step_cost(2, 0), None);
+            assert_eq!(metric.step_cost(1, 2), None);
+        }
+    }
+
+    #[test]
+    fn get_place_weights_only_top_three_pays_by_place() {
+        assert_eq!(PrizeSplit::TopThree.get_place_weights(), vec![50, 30, 20]);
+        assert!(PrizeSplit::WinnerTakesAll.get_place_weights().is_empty());
+        assert!(PrizeSplit::Proportional.get_place_weights().is_empty());
+    }
+}